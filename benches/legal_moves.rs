@@ -0,0 +1,52 @@
+//! Compare full moves map with per-square queries on 12x12 positions.
+//!
+//! Run with `cargo bench --bench legal_moves`.
+
+use shuuro::Variant;
+use shuuro_wasm::PositionContainer;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ROUNDS: u32 = 2000;
+
+const POSITIONS: [&str; 3] = [
+    "57/2rnbqkbnr2/2pppppppp2/57/57/57/57/57/57/2PPPPPPPP2/2RNBQKBNR2/57 w - 1",
+    "57/2rnbqkbnr2/2pp1ppppp2/4p7/57/3L05L02/57/5N6/57/2PPP1PPPP2/2RNBQKB1R2/57 w - 1",
+    "55k1/57/57/57/57/3L05L02/57/57/57/57/57/Q55K w - 1",
+];
+
+fn measure(name: &str, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        f();
+    }
+    let elapsed: Duration = start.elapsed();
+    println!("{:<20} {:>10.2?} per call", name, elapsed / ROUNDS);
+}
+
+fn main() {
+    for sfen in POSITIONS {
+        let mut pos = PositionContainer::new(Variant::from(&String::from("shuuro")));
+        pos.set_sfen(sfen).unwrap();
+        let moves = pos.legal_moves(0);
+        let mut squares: Vec<&String> = moves.keys().collect();
+        squares.sort();
+        let from = squares[0].clone();
+        let game_move = format!("{}_{}", from, moves[&from][0]);
+
+        println!("{}", sfen);
+        measure("legal_moves", || {
+            black_box(pos.legal_moves(black_box(0)));
+        });
+        measure("legal_moves_from", || {
+            black_box(pos.legal_moves_from(black_box(&from)));
+        });
+        measure("is_legal", || {
+            black_box(pos.is_legal(black_box(&game_move)));
+        });
+        measure("empty square", || {
+            black_box(pos.legal_moves_from(black_box("f6")));
+        });
+        println!();
+    }
+}
@@ -0,0 +1,428 @@
+use js_sys::Array;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::notation::{MoveParseErrorJS, MoveParseErrorKind};
+use crate::position::ShuuroPosition;
+use crate::position_container::PositionContainer;
+use crate::record::{section, tag_line, wrap};
+use crate::types::{AnalysisNodeValue, StringList};
+
+/// Move in analysis tree. First child continues main line, others are variations.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalysisNode {
+    pub id: usize,
+    pub game_move: String,
+    pub san: String,
+    pub side: String,
+    pub comment: Option<String>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+/// Move numbers while writing tree.
+#[derive(Clone, Copy)]
+struct Numbering {
+    number: usize,
+    white_moved: bool,
+}
+
+impl Numbering {
+    fn new() -> Self {
+        Self {
+            number: 1,
+            white_moved: false,
+        }
+    }
+}
+
+/// Class for analysis board with variations. Node 0 is starting position.
+/// Every method with position keeps that position at current node.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct ShuuroAnalysis {
+    variant: String,
+    start: String,
+    nodes: Vec<Option<AnalysisNode>>,
+    current: usize,
+}
+
+#[wasm_bindgen]
+impl ShuuroAnalysis {
+    /// Tree for position in deploy or fight, given as sfen.
+    #[wasm_bindgen(constructor)]
+    pub fn new(variant: &str, sfen: &str) -> Self {
+        let root = AnalysisNode {
+            id: 0,
+            game_move: String::new(),
+            san: String::new(),
+            side: String::new(),
+            comment: None,
+            parent: None,
+            children: vec![],
+        };
+        Self {
+            variant: String::from(variant),
+            start: String::from(sfen),
+            nodes: vec![Some(root)],
+            current: 0,
+        }
+    }
+
+    /// Play move from current node. Existing child is reused, new move starts variation.
+    /// Move can be in sfen format or SAN. Returns id of node.
+    #[wasm_bindgen]
+    pub fn play(
+        &mut self,
+        position: &mut ShuuroPosition,
+        game_move: &str,
+    ) -> Result<usize, JsValue> {
+        self.play_move(position.container_mut(), game_move)
+            .map_err(|e| e.to_js())
+    }
+
+    #[wasm_bindgen]
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Go to node and set position after its move.
+    #[wasm_bindgen]
+    pub fn goto(&mut self, position: &mut ShuuroPosition, id: usize) -> bool {
+        self.goto_node(position.container_mut(), id)
+    }
+
+    /// Go to parent of current node.
+    #[wasm_bindgen]
+    pub fn back(&mut self, position: &mut ShuuroPosition) -> bool {
+        match self.node(self.current).and_then(|n| n.parent) {
+            Some(parent) => self.goto_node(position.container_mut(), parent),
+            None => false,
+        }
+    }
+
+    /// Go to next move in line of current node.
+    #[wasm_bindgen]
+    pub fn forward(&mut self, position: &mut ShuuroPosition) -> bool {
+        match self.node(self.current).and_then(|n| n.children.first()) {
+            Some(child) => {
+                let child = *child;
+                self.goto_node(position.container_mut(), child)
+            }
+            None => false,
+        }
+    }
+
+    /// Node with move, comment, parent and children, or null if it does not exist.
+    #[wasm_bindgen]
+    pub fn get_node(&self, id: usize) -> AnalysisNodeValue {
+        let node = self.node(id).map(serde_wasm_bindgen::to_value);
+        match node {
+            Some(Ok(node)) => node.unchecked_into(),
+            _ => JsValue::NULL.unchecked_into(),
+        }
+    }
+
+    /// Make node and its ancestors first child, so they become main line.
+    #[wasm_bindgen]
+    pub fn promote(&mut self, id: usize) -> bool {
+        if self.node(id).is_none() {
+            return false;
+        }
+        let mut child = id;
+        while let Some(parent) = self.node(child).and_then(|n| n.parent) {
+            if let Some(Some(parent)) = self.nodes.get_mut(parent) {
+                parent.children.retain(|c| *c != child);
+                parent.children.insert(0, child);
+            }
+            child = parent;
+        }
+        true
+    }
+
+    /// Delete node with all moves after it. Root can not be deleted.
+    #[wasm_bindgen]
+    pub fn delete(&mut self, position: &mut ShuuroPosition, id: usize) -> bool {
+        self.delete_node(position.container_mut(), id)
+    }
+
+    #[wasm_bindgen]
+    pub fn set_comment(&mut self, id: usize, comment: Option<String>) -> bool {
+        match self.nodes.get_mut(id) {
+            Some(Some(node)) => {
+                node.comment = comment;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves of main line in sfen format.
+    #[wasm_bindgen]
+    pub fn mainline(&self) -> StringList {
+        let ar = Array::new();
+        for m in self.mainline_moves() {
+            ar.push(&JsValue::from_str(&m));
+        }
+        ar.unchecked_into()
+    }
+
+    /// Tree in game record format, with variations in parentheses.
+    #[wasm_bindgen]
+    pub fn write(&self) -> String {
+        self.write_text()
+    }
+}
+
+impl ShuuroAnalysis {
+    pub fn node(&self, id: usize) -> Option<&AnalysisNode> {
+        self.nodes.get(id).and_then(|n| n.as_ref())
+    }
+
+    pub fn play_move(
+        &mut self,
+        position: &mut PositionContainer,
+        game_move: &str,
+    ) -> Result<usize, MoveParseErrorJS> {
+        let game_move = position.parse_move(game_move)?;
+        let existing = self.node(self.current).and_then(|n| {
+            n.children
+                .iter()
+                .find(|c| {
+                    self.node(**c)
+                        .map(|c| c.game_move == game_move)
+                        .unwrap_or(false)
+                })
+                .copied()
+        });
+        let san = position
+            .to_san(&game_move, false)
+            .unwrap_or_else(|| game_move.clone());
+        let side = position.side_to_move();
+        if !apply(position, &game_move) {
+            return Err(MoveParseErrorJS::new(
+                MoveParseErrorKind::Illegal,
+                &format!("'{}' can not be played", game_move),
+            ));
+        }
+        if let Some(existing) = existing {
+            self.current = existing;
+            return Ok(existing);
+        }
+        let id = self.nodes.len();
+        self.nodes.push(Some(AnalysisNode {
+            id,
+            game_move,
+            san,
+            side,
+            comment: None,
+            parent: Some(self.current),
+            children: vec![],
+        }));
+        if let Some(Some(parent)) = self.nodes.get_mut(self.current) {
+            parent.children.push(id);
+        }
+        self.current = id;
+        Ok(id)
+    }
+
+    /// Set position to starting sfen and play every move until node.
+    pub fn goto_node(&mut self, position: &mut PositionContainer, id: usize) -> bool {
+        let path = match self.path(id) {
+            Some(path) => path,
+            None => return false,
+        };
+        if position.set_sfen(&self.start).is_err() {
+            return false;
+        }
+        for node in path {
+            if let Some(node) = self.node(node) {
+                if !apply(position, &node.game_move) {
+                    return false;
+                }
+            }
+        }
+        self.current = id;
+        true
+    }
+
+    pub fn delete_node(&mut self, position: &mut PositionContainer, id: usize) -> bool {
+        let parent = match self.node(id).and_then(|n| n.parent) {
+            Some(parent) => parent,
+            None => return false,
+        };
+        let current = self.path(self.current).unwrap_or_default();
+        if current.contains(&id) {
+            self.goto_node(position, parent);
+        }
+        if let Some(Some(parent)) = self.nodes.get_mut(parent) {
+            parent.children.retain(|c| *c != id);
+        }
+        let mut removed = vec![id];
+        while let Some(node) = removed.pop() {
+            if let Some(Some(node)) = self.nodes.get_mut(node).map(|n| n.take()) {
+                removed.extend(node.children);
+            }
+        }
+        true
+    }
+
+    pub fn mainline_moves(&self) -> Vec<String> {
+        let mut moves = vec![];
+        let mut node = self.node(0);
+        while let Some(child) = node.and_then(|n| n.children.first()) {
+            node = self.node(*child);
+            if let Some(node) = node {
+                moves.push(node.game_move.clone());
+            }
+        }
+        moves
+    }
+
+    pub fn write_text(&self) -> String {
+        let mut text = tag_line("Variant", &self.variant);
+        text.push_str(&tag_line("Sfen", &self.start));
+        text.push_str(&tag_line("Result", "*"));
+        if let Some(comment) = self.node(0).and_then(|n| n.comment.as_ref()) {
+            text.push_str(&format!("\n{{{}}}\n", comment));
+        }
+        let mut sections: Vec<(&str, Vec<String>)> = vec![];
+        self.main_line(0, Numbering::new(), false, &mut sections);
+        for (header, tokens) in sections {
+            text.push_str(&format!("\n{}\n", header));
+            text.push_str(&wrap(&join_parentheses(tokens)));
+            text.push('\n');
+        }
+        text.push_str("\n*\n");
+        text
+    }
+
+    /// Nodes from first move until node.
+    fn path(&self, id: usize) -> Option<Vec<usize>> {
+        let mut path = vec![];
+        let mut node = self.node(id)?;
+        while let Some(parent) = node.parent {
+            path.push(node.id);
+            node = self.node(parent)?;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Main line after node. New section starts when phase changes.
+    fn main_line(
+        &self,
+        id: usize,
+        numbering: Numbering,
+        force: bool,
+        sections: &mut Vec<(&'static str, Vec<String>)>,
+    ) {
+        let children = match self.node(id) {
+            Some(node) if !node.children.is_empty() => &node.children,
+            _ => return,
+        };
+        let main = children[0];
+        let mut numbering = numbering;
+        if let Some(node) = self.node(main) {
+            let header = section(&node.game_move);
+            if sections.last().map(|s| s.0) != Some(header) {
+                sections.push((header, vec![]));
+                numbering = Numbering::new();
+            }
+        }
+        if let Some((_, tokens)) = sections.last_mut() {
+            let after = self.branches(children, numbering, force, tokens);
+            self.main_line(main, after, children.len() > 1, sections);
+        }
+    }
+
+    /// Line after node inside variation.
+    fn variation(&self, id: usize, numbering: Numbering, force: bool, tokens: &mut Vec<String>) {
+        if let Some(node) = self.node(id) {
+            if !node.children.is_empty() {
+                let children = &node.children;
+                let after = self.branches(children, numbering, force, tokens);
+                self.variation(children[0], after, children.len() > 1, tokens);
+            }
+        }
+    }
+
+    /// First child, then other children in parentheses. Returns numbering after first child.
+    fn branches(
+        &self,
+        children: &[usize],
+        numbering: Numbering,
+        force: bool,
+        tokens: &mut Vec<String>,
+    ) -> Numbering {
+        let mut after = numbering;
+        self.move_tokens(children[0], &mut after, force, tokens);
+        for child in children[1..].iter() {
+            let mut line = numbering;
+            tokens.push(String::from("("));
+            self.move_tokens(*child, &mut line, true, tokens);
+            self.variation(*child, line, false, tokens);
+            tokens.push(String::from(")"));
+        }
+        after
+    }
+
+    fn move_tokens(
+        &self,
+        id: usize,
+        numbering: &mut Numbering,
+        force: bool,
+        tokens: &mut Vec<String>,
+    ) {
+        let node = match self.node(id) {
+            Some(node) => node,
+            None => return,
+        };
+        if node.side == "w" {
+            if numbering.white_moved {
+                numbering.number += 1;
+            }
+            tokens.push(format!("{}.", numbering.number));
+        } else if force || !numbering.white_moved {
+            tokens.push(format!("{}...", numbering.number));
+        }
+        numbering.white_moved = node.side == "w";
+        if node.side == "b" {
+            numbering.number += 1;
+        }
+        tokens.push(node.san.clone());
+        if let Some(comment) = &node.comment {
+            tokens.push(format!("{{{}}}", comment));
+        }
+    }
+}
+
+/// Place or play move. Returns false if move is not legal.
+fn apply(position: &mut PositionContainer, game_move: &str) -> bool {
+    if game_move.contains('@') {
+        position.place(String::from(game_move)).is_some()
+    } else {
+        position.play_move(game_move).is_some()
+    }
+}
+
+/// `(` is joined with next token and `)` with previous.
+fn join_parentheses(tokens: Vec<String>) -> Vec<String> {
+    let mut joined: Vec<String> = vec![];
+    let mut open = false;
+    for token in tokens {
+        if token == "(" {
+            open = true;
+        } else if token == ")" {
+            if let Some(last) = joined.last_mut() {
+                last.push(')');
+            }
+        } else if open {
+            joined.push(format!("({}", token));
+            open = false;
+        } else {
+            joined.push(token);
+        }
+    }
+    joined
+}
@@ -0,0 +1,190 @@
+use shuuro::{
+    attacks::Attacks, bitboard::BitBoard, position::Position, Color, Piece, PieceType, Square,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use crate::local_position::LocalPosition;
+use crate::sfen::{board_size, square_coords};
+
+/// Plain copy of board, used by heuristics. Squares are (file from 0, rank from 1).
+pub struct BoardView {
+    pub size: usize,
+    pub pieces: HashMap<(usize, usize), Piece>,
+    pub plinths: HashSet<(usize, usize)>,
+}
+
+impl BoardView {
+    pub fn piece(&self, sq: (usize, usize)) -> Option<&Piece> {
+        self.pieces.get(&sq)
+    }
+
+    pub fn is_plinth(&self, sq: (usize, usize)) -> bool {
+        self.plinths.contains(&sq)
+    }
+
+    /// Rank counted from side of color.
+    pub fn relative_rank(&self, rank: usize, color: Color) -> usize {
+        match color {
+            Color::Black => self.size + 1 - rank,
+            _ => rank,
+        }
+    }
+
+    /// Bigger number is closer to center.
+    pub fn centrality(&self, sq: (usize, usize)) -> i32 {
+        let center = self.size as i32 - 1;
+        let file = (2 * sq.0 as i32 - center).abs();
+        let rank = (2 * (sq.1 as i32 - 1) - center).abs();
+        center - (file + rank) / 2
+    }
+
+    pub fn neighbours(&self, sq: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut squares = vec![];
+        for df in -1..=1 {
+            for dr in -1..=1 {
+                if df == 0 && dr == 0 {
+                    continue;
+                }
+                if let Some(sq) = self.offset(sq, df, dr) {
+                    squares.push(sq);
+                }
+            }
+        }
+        squares
+    }
+
+    pub fn offset(&self, sq: (usize, usize), df: i32, dr: i32) -> Option<(usize, usize)> {
+        let file = sq.0 as i32 + df;
+        let rank = sq.1 as i32 + dr;
+        let size = self.size as i32;
+        if file < 0 || file >= size || rank < 1 || rank > size {
+            return None;
+        }
+        Some((file as usize, rank as usize))
+    }
+
+    pub fn king(&self, color: Color) -> Option<(usize, usize)> {
+        self.pieces
+            .iter()
+            .find(|(_, p)| p.piece_type == PieceType::King && p.color == color)
+            .map(|(sq, _)| *sq)
+    }
+}
+
+/// Pieces and plinths of position, read from its bitboards.
+pub struct Occupancy<S> {
+    pub pieces: HashMap<S, Piece>,
+    pub plinths: HashSet<S>,
+}
+
+impl<S: Square + Hash> Occupancy<S> {
+    /// Pieces and plinths both stop sliders.
+    pub fn blocks(&self, sq: &S) -> bool {
+        self.pieces.contains_key(sq) || self.plinths.contains(sq)
+    }
+
+    pub fn king(&self, color: Color) -> Option<S> {
+        self.pieces
+            .iter()
+            .find(|(_, p)| p.piece_type == PieceType::King && p.color == color)
+            .map(|(sq, _)| *sq)
+    }
+}
+
+impl<S, B, A, P> LocalPosition<S, B, A, P>
+where
+    S: Square + Hash,
+    B: BitBoard<S>,
+    A: Attacks<S, B>,
+    P: Position<S, B, A> + Clone,
+{
+    pub(crate) fn board_view(state: &P) -> BoardView {
+        let mut view = BoardView {
+            size: board_size(state.variant()),
+            pieces: HashMap::new(),
+            plinths: HashSet::new(),
+        };
+        for sq in state.player_bb(Color::NoColor) {
+            if let Some(coords) = square_coords(&sq.to_string()) {
+                view.plinths.insert(coords);
+            }
+        }
+        for color in [Color::White, Color::Black] {
+            for sq in state.player_bb(color) {
+                let coords = square_coords(&sq.to_string());
+                if let (Some(coords), Some(piece)) = (coords, state.piece_at(sq)) {
+                    let piece = Piece {
+                        piece_type: piece.piece_type,
+                        color: piece.color,
+                    };
+                    view.pieces.insert(coords, piece);
+                }
+            }
+        }
+        view
+    }
+    pub(crate) fn occupancy(state: &P) -> Occupancy<S> {
+        let mut occupancy = Occupancy {
+            pieces: HashMap::new(),
+            plinths: state.player_bb(Color::NoColor).into_iter().collect(),
+        };
+        for color in [Color::White, Color::Black] {
+            for sq in state.player_bb(color) {
+                if let Some(piece) = state.piece_at(sq) {
+                    let piece = Piece {
+                        piece_type: piece.piece_type,
+                        color: piece.color,
+                    };
+                    occupancy.pieces.insert(sq, piece);
+                }
+            }
+        }
+        occupancy
+    }
+
+    /// Squares attacked by piece, from `Attacks` of variant. Sliders stop at first
+    /// piece and before plinth, only knight jumps can reach plinth.
+    pub(crate) fn piece_attacks(occupancy: &Occupancy<S>, sq: S, piece: &Piece) -> Vec<S> {
+        let mut squares = vec![];
+        for (piece_type, slides) in attack_parts(piece.piece_type) {
+            if slides {
+                let targets = A::get_sliding_attacks(piece_type, &sq, B::empty());
+                squares.extend(targets.into_iter().filter(|to| {
+                    !occupancy.plinths.contains(to)
+                        && !A::between(sq, *to)
+                            .into_iter()
+                            .any(|b| occupancy.blocks(&b))
+                }));
+            } else {
+                let targets = A::get_non_sliding_attacks(piece_type, &sq, piece.color, B::empty());
+                let jumps = piece_type == PieceType::Knight;
+                squares.extend(
+                    targets
+                        .into_iter()
+                        .filter(|to| jumps || !occupancy.plinths.contains(to)),
+                );
+            }
+        }
+        squares
+    }
+}
+
+/// File from 0 and rank from 1 of square.
+pub(crate) fn coords<S: Square>(sq: &S) -> (usize, usize) {
+    square_coords(&sq.to_string()).unwrap_or_default()
+}
+
+/// Attacks that make attacks of piece type, and if they slide.
+/// Fairy pieces are rook or bishop together with knight.
+pub(crate) fn attack_parts(piece_type: PieceType) -> Vec<(PieceType, bool)> {
+    match piece_type {
+        PieceType::Queen | PieceType::Rook | PieceType::Bishop => vec![(piece_type, true)],
+        PieceType::Chancellor => vec![(PieceType::Rook, true), (PieceType::Knight, false)],
+        PieceType::ArchBishop => vec![(PieceType::Bishop, true), (PieceType::Knight, false)],
+        PieceType::Plinth => vec![],
+        _ => vec![(piece_type, false)],
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use shuuro::{
+    piece_type::PieceTypeIter, shuuro12::square12::Square12, Color, Move, Piece, PieceType,
+    Selection,
+};
+use std::str::FromStr;
+
+use crate::selection::piece_price;
+
+/// Pawns bought before anything else, so army can defend its king.
+const MIN_PAWNS: u8 = 4;
+
+/// Style of army bought by computer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShopStyle {
+    Balanced,
+    Aggressive,
+    FairyHeavy,
+}
+
+impl FromStr for ShopStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "balanced" => Ok(ShopStyle::Balanced),
+            "aggressive" => Ok(ShopStyle::Aggressive),
+            "fairyHeavy" => Ok(ShopStyle::FairyHeavy),
+            _ => Err(format!("unknown shop style: {}", s)),
+        }
+    }
+}
+
+impl ShopStyle {
+    /// How often piece type is picked.
+    fn weight(&self, piece_type: PieceType) -> u64 {
+        let weights = match self {
+            // Q, R, B, N, P, C, A, G
+            ShopStyle::Balanced => [2, 3, 3, 3, 8, 1, 1, 1],
+            ShopStyle::Aggressive => [5, 4, 2, 2, 3, 2, 2, 2],
+            ShopStyle::FairyHeavy => [1, 2, 2, 2, 5, 5, 5, 5],
+        };
+        match piece_type {
+            PieceType::Queen => weights[0],
+            PieceType::Rook => weights[1],
+            PieceType::Bishop => weights[2],
+            PieceType::Knight => weights[3],
+            PieceType::Pawn => weights[4],
+            PieceType::Chancellor => weights[5],
+            PieceType::ArchBishop => weights[6],
+            PieceType::Giraffe => weights[7],
+            _ => 0,
+        }
+    }
+}
+
+/// Xorshift generator, so same seed gives same army.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self((seed ^ 0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Purchases in `+X` format that spend credit of player.
+/// Shop is not changed, moves are played on a copy.
+pub fn plan_army(
+    shop: &Selection<Square12>,
+    color: Color,
+    style: ShopStyle,
+    seed: u64,
+) -> Vec<String> {
+    let variant = shop.variant();
+    let mut scratch = Selection::<Square12>::default();
+    scratch.update_variant(variant);
+    for m in shop.get_sfen_history(&color).iter() {
+        if let Some(m) = Move::from_sfen(&m.0) {
+            scratch.play(m);
+        }
+    }
+
+    let mut rng = Rng::new(seed);
+    let mut candidates: Vec<(PieceType, i32)> = PieceTypeIter::default()
+        .filter(|p| style.weight(*p) > 0 && variant.can_select(p))
+        .map(|p| (p, piece_price(variant, p)))
+        .filter(|p| p.1 > 0)
+        .collect();
+    let mut moves = vec![];
+    let pawn = Piece {
+        piece_type: PieceType::Pawn,
+        color,
+    };
+    while scratch.get(pawn) < MIN_PAWNS {
+        if !buy(&mut scratch, pawn, &mut moves) {
+            break;
+        }
+    }
+    loop {
+        let credit = scratch.credit(color);
+        candidates.retain(|p| p.1 <= credit);
+        let total: u64 = candidates.iter().map(|p| style.weight(p.0)).sum();
+        if total == 0 {
+            break;
+        }
+        let mut pick = rng.next_u64() % total;
+        let mut index = 0;
+        for (i, p) in candidates.iter().enumerate() {
+            let weight = style.weight(p.0);
+            if pick < weight {
+                index = i;
+                break;
+            }
+            pick -= weight;
+        }
+        let piece = Piece {
+            piece_type: candidates[index].0,
+            color,
+        };
+        if !buy(&mut scratch, piece, &mut moves) {
+            // limit for this piece is reached
+            candidates.remove(index);
+        }
+    }
+    moves
+}
+
+fn buy(shop: &mut Selection<Square12>, piece: Piece, moves: &mut Vec<String>) -> bool {
+    let before = shop.get(piece);
+    shop.play(Move::Select { piece });
+    if shop.get(piece) > before {
+        moves.push(format!("+{}", piece));
+        return true;
+    }
+    false
+}
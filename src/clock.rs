@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use shuuro::Color;
+
+use crate::game::Phase;
+
+/// How time is counted for every move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClockKind {
+    SuddenDeath,
+    Fischer,
+    Bronstein,
+    Delay,
+}
+
+/// Time for one phase in milliseconds. Increment is bonus for Fischer,
+/// and delay for Bronstein and simple delay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseControl {
+    pub kind: ClockKind,
+    pub base: u64,
+    pub increment: u64,
+}
+
+impl PhaseControl {
+    /// Time taken from clock for move that lasted `elapsed`.
+    fn charged(&self, elapsed: u64) -> u64 {
+        match self.kind {
+            ClockKind::Delay => elapsed.saturating_sub(self.increment),
+            _ => elapsed,
+        }
+    }
+
+    /// Time added to clock after move.
+    fn bonus(&self, elapsed: u64) -> u64 {
+        match self.kind {
+            ClockKind::Fischer => self.increment,
+            ClockKind::Bronstein => elapsed.min(self.increment),
+            _ => 0,
+        }
+    }
+}
+
+/// Budget for every phase. Time left in one phase is not carried to next one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeControl {
+    pub shop: PhaseControl,
+    pub deploy: PhaseControl,
+    pub fight: PhaseControl,
+}
+
+impl TimeControl {
+    pub fn phase(&self, phase: Phase) -> Option<&PhaseControl> {
+        match phase {
+            Phase::Shop => Some(&self.shop),
+            Phase::Deploy => Some(&self.deploy),
+            Phase::Fight => Some(&self.fight),
+            Phase::Finished => None,
+        }
+    }
+}
+
+/// Time of one player. Started is timestamp when clock started running.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SideClock {
+    pub remaining: u64,
+    pub started: Option<u64>,
+}
+
+/// Time left for both players at some moment.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockView {
+    pub phase: Phase,
+    pub white: u64,
+    pub black: u64,
+    pub running: Vec<String>,
+    pub flagged: Option<String>,
+}
+
+/// Chess clock for both players. Every method gets current time in milliseconds,
+/// so clock never reads system time.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShuuroClock {
+    pub control: TimeControl,
+    pub phase: Phase,
+    pub white: SideClock,
+    pub black: SideClock,
+    pub flagged: Option<String>,
+}
+
+impl ShuuroClock {
+    /// Clock with shop budget. Nothing runs until phase is started.
+    pub fn new(control: TimeControl) -> Self {
+        let side = SideClock {
+            remaining: control.shop.base,
+            started: None,
+        };
+        Self {
+            control,
+            phase: Phase::Shop,
+            white: side,
+            black: side,
+            flagged: None,
+        }
+    }
+
+    /// Reset both clocks to budget of phase. In shop both clocks run,
+    /// in deploy and fight only clock of side to move.
+    pub fn start_phase(&mut self, phase: Phase, side: Color, now: u64) {
+        self.phase = phase;
+        let remaining = self.control.phase(phase).map(|c| c.base).unwrap_or(0);
+        for color in [Color::White, Color::Black] {
+            if let Some(clock) = self.side_mut(color) {
+                *clock = SideClock {
+                    remaining,
+                    started: None,
+                };
+            }
+        }
+        match phase {
+            Phase::Shop => {
+                self.start(Color::White, now);
+                self.start(Color::Black, now);
+            }
+            Phase::Deploy | Phase::Fight => self.start(side, now),
+            Phase::Finished => (),
+        }
+    }
+
+    pub fn start(&mut self, side: Color, now: u64) {
+        if self.flagged.is_some() {
+            return;
+        }
+        if let Some(clock) = self.side_mut(side) {
+            clock.started.get_or_insert(now);
+        }
+    }
+
+    /// Stop clock of side after its move and add bonus.
+    /// Returns false if time ran out before move, then both clocks are stopped.
+    pub fn press(&mut self, side: Color, now: u64) -> bool {
+        if self.flagged.is_some() {
+            return false;
+        }
+        let control = match self.control.phase(self.phase) {
+            Some(control) => *control,
+            None => return true,
+        };
+        let clock = match self.side_mut(side) {
+            Some(clock) => clock,
+            None => return false,
+        };
+        let started = match clock.started.take() {
+            Some(started) => started,
+            None => return true,
+        };
+        let elapsed = now.saturating_sub(started);
+        let charged = control.charged(elapsed);
+        if charged >= clock.remaining {
+            clock.remaining = 0;
+            self.flagged = Some(color_name(side));
+            self.stop(now);
+            return false;
+        }
+        clock.remaining = clock.remaining - charged + control.bonus(elapsed);
+        true
+    }
+
+    /// Stop both clocks without bonus, when game is over.
+    pub fn stop(&mut self, now: u64) {
+        for color in [Color::White, Color::Black] {
+            let remaining = self.remaining(color, now);
+            if let Some(clock) = self.side_mut(color) {
+                clock.remaining = remaining;
+                clock.started = None;
+            }
+        }
+        self.phase = Phase::Finished;
+    }
+
+    /// Time left for side at `now`.
+    pub fn remaining(&self, side: Color, now: u64) -> u64 {
+        let clock = match self.side(side) {
+            Some(clock) => clock,
+            None => return 0,
+        };
+        match (clock.started, self.control.phase(self.phase)) {
+            (Some(started), Some(control)) => clock
+                .remaining
+                .saturating_sub(control.charged(now.saturating_sub(started))),
+            _ => clock.remaining,
+        }
+    }
+
+    /// Side whose time ran out. Running clocks are checked at `now`.
+    pub fn check_flag(&mut self, now: u64) -> Option<String> {
+        if self.flagged.is_none() {
+            for color in [Color::White, Color::Black] {
+                if self.is_running(color) && self.remaining(color, now) == 0 {
+                    self.flagged = Some(color_name(color));
+                    self.stop(now);
+                    break;
+                }
+            }
+        }
+        self.flagged.clone()
+    }
+
+    pub fn is_running(&self, side: Color) -> bool {
+        self.side(side)
+            .map(|clock| clock.started.is_some())
+            .unwrap_or(false)
+    }
+
+    pub fn view(&self, now: u64) -> ClockView {
+        let running = [Color::White, Color::Black]
+            .into_iter()
+            .filter(|color| self.is_running(*color))
+            .map(color_name)
+            .collect();
+        ClockView {
+            phase: self.phase,
+            white: self.remaining(Color::White, now),
+            black: self.remaining(Color::Black, now),
+            running,
+            flagged: self.flagged.clone(),
+        }
+    }
+
+    fn side(&self, side: Color) -> Option<&SideClock> {
+        match side {
+            Color::White => Some(&self.white),
+            Color::Black => Some(&self.black),
+            _ => None,
+        }
+    }
+
+    fn side_mut(&mut self, side: Color) -> Option<&mut SideClock> {
+        match side {
+            Color::White => Some(&mut self.white),
+            Color::Black => Some(&mut self.black),
+            _ => None,
+        }
+    }
+}
+
+fn color_name(color: Color) -> String {
+    match color {
+        Color::White => String::from("white"),
+        _ => String::from("black"),
+    }
+}
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use shuuro::{attacks::Attacks, bitboard::BitBoard, position::Position, Color, Square};
+use std::{collections::HashSet, hash::Hash};
+
+use crate::board::{attack_parts, coords, Occupancy};
+use crate::local_position::{opponent, LocalPosition};
+use crate::sfen::square_coords;
+
+/// Piece standing between king and enemy slider.
+/// Ray goes from square next to king until slider.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pin {
+    pub square: String,
+    pub pinner: String,
+    pub ray: Vec<String>,
+}
+
+/// Checks and pins for side to move.
+/// Discovered are pieces of side to move that uncover check when they move.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Threats {
+    pub checkers: Vec<String>,
+    pub check_rays: Vec<Vec<String>>,
+    pub blocks: Vec<String>,
+    pub pins: Vec<Pin>,
+    pub discovered: Vec<Pin>,
+}
+
+impl<S, B, A, P> LocalPosition<S, B, A, P>
+where
+    S: Square + Hash,
+    B: BitBoard<S>,
+    A: Attacks<S, B>,
+    P: Position<S, B, A> + Clone,
+{
+    /// Squares attacked by color, including squares with own pieces.
+    pub fn attacked_squares(&self, color: Color) -> Vec<String> {
+        let occupancy = Self::occupancy(&self.state);
+        let mut squares = HashSet::new();
+        for (sq, piece) in occupancy.pieces.iter().filter(|(_, p)| p.color == color) {
+            squares.extend(Self::piece_attacks(&occupancy, *sq, piece));
+        }
+        names(squares)
+    }
+
+    /// Squares of pieces from both sides that attack square.
+    pub fn attackers_of(&self, square: &str) -> Vec<String> {
+        let occupancy = Self::occupancy(&self.state);
+        let attackers = occupancy.pieces.iter().filter(|(sq, piece)| {
+            Self::piece_attacks(&occupancy, **sq, piece)
+                .iter()
+                .any(|to| to.to_string() == square)
+        });
+        names(attackers.map(|(sq, _)| *sq))
+    }
+
+    /// Checkers, squares that block check and pins for side to move.
+    /// Blocks are empty in double check.
+    pub fn threats(&self) -> Threats {
+        let occupancy = Self::occupancy(&self.state);
+        let stm = self.state.side_to_move();
+        let enemy = opponent(stm);
+        let mut threats = Threats::default();
+        if let Some(king) = occupancy.king(stm) {
+            let mut checkers: Vec<S> = occupancy
+                .pieces
+                .iter()
+                .filter(|(sq, p)| {
+                    p.color == enemy && Self::piece_attacks(&occupancy, **sq, p).contains(&king)
+                })
+                .map(|(sq, _)| *sq)
+                .collect();
+            checkers.sort_by_key(coords);
+            for checker in checkers {
+                let ray = Self::check_ray(king, checker);
+                threats.checkers.push(checker.to_string());
+                threats
+                    .check_rays
+                    .push(ray.iter().map(|sq| sq.to_string()).collect());
+                if let Some((_, between)) = ray.split_last() {
+                    threats
+                        .blocks
+                        .extend(between.iter().map(|sq| sq.to_string()));
+                }
+            }
+            if threats.checkers.len() > 1 {
+                threats.blocks.clear();
+            }
+            threats.pins = Self::x_rays(&occupancy, king, stm, enemy);
+        }
+        if let Some(king) = occupancy.king(enemy) {
+            threats.discovered = Self::x_rays(&occupancy, king, stm, stm);
+        }
+        threats
+    }
+
+    /// Squares from king until checker. Checker that does not slide to king
+    /// has nothing between, so ray is only its square.
+    fn check_ray(king: S, checker: S) -> Vec<S> {
+        let mut ray = Self::between(king, checker);
+        ray.push(checker);
+        ray
+    }
+
+    /// Squares between two squares on same line, ordered from first square.
+    fn between(from: S, to: S) -> Vec<S> {
+        let start = coords(&from);
+        let mut squares: Vec<S> = A::between(from, to).into_iter().collect();
+        squares.sort_by_key(|sq| {
+            let sq = coords(sq);
+            sq.0.abs_diff(start.0).max(sq.1.abs_diff(start.1))
+        });
+        squares
+    }
+
+    /// Pieces of blocker color that are only thing between king
+    /// and slider of slider color.
+    fn x_rays(occupancy: &Occupancy<S>, king: S, blocker: Color, slider: Color) -> Vec<Pin> {
+        let mut pins = vec![];
+        for (pinner, piece) in occupancy.pieces.iter().filter(|(_, p)| p.color == slider) {
+            let aligned = attack_parts(piece.piece_type)
+                .into_iter()
+                .filter(|part| part.1)
+                .any(|part| {
+                    A::get_sliding_attacks(part.0, pinner, B::empty())
+                        .into_iter()
+                        .any(|sq| sq == king)
+                });
+            if !aligned {
+                continue;
+            }
+            let between = Self::between(king, *pinner);
+            if between.iter().any(|sq| occupancy.plinths.contains(sq)) {
+                continue;
+            }
+            let mut blockers = between
+                .iter()
+                .filter(|sq| occupancy.pieces.contains_key(*sq));
+            let square = match (blockers.next(), blockers.next()) {
+                (Some(sq), None) if occupancy.pieces[sq].color == blocker => *sq,
+                _ => continue,
+            };
+            let ray = between.iter().chain([pinner]);
+            pins.push(Pin {
+                square: square.to_string(),
+                pinner: pinner.to_string(),
+                ray: ray.map(|sq| sq.to_string()).collect(),
+            });
+        }
+        pins.sort_by_key(|pin| square_coords(&pin.square));
+        pins
+    }
+}
+
+/// Square names sorted by file and then rank.
+fn names<S: Square>(squares: impl IntoIterator<Item = S>) -> Vec<String> {
+    let mut names: Vec<((usize, usize), String)> = squares
+        .into_iter()
+        .map(|sq| {
+            let name = sq.to_string();
+            (square_coords(&name).unwrap_or_default(), name)
+        })
+        .collect();
+    names.sort();
+    names.into_iter().map(|n| n.1).collect()
+}
@@ -0,0 +1,159 @@
+use shuuro::{
+    attacks::Attacks, bitboard::BitBoard, position::Position, Color, Move, Piece, PieceType, Square,
+};
+use std::hash::Hash;
+
+use crate::board::BoardView;
+use crate::local_position::{opponent, LocalPosition};
+use crate::sfen::square_coords;
+
+/// How many best placements are checked against reply on strongest level.
+const LOOKAHEAD: usize = 6;
+
+impl<S, B, A, P> LocalPosition<S, B, A, P>
+where
+    S: Square + Hash,
+    B: BitBoard<S>,
+    A: Attacks<S, B>,
+    P: Position<S, B, A> + Clone,
+{
+    /// Placement for side to move in `P@sq` format.
+    /// Level 1 adds noise from seed to heuristic, level 2 uses heuristic
+    /// and level 3 also checks best reply of opponent.
+    pub fn deploy_move(&self, level: u8, seed: u64) -> Option<String> {
+        let mut state = self.state.clone();
+        let mut placements = Self::placements(&mut state);
+        match level {
+            0 | 1 => {
+                for placement in placements.iter_mut() {
+                    placement.1 += jitter(&placement.0, seed);
+                }
+                placements.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                placements.into_iter().next().map(|p| p.0)
+            }
+            2 => placements.into_iter().next().map(|p| p.0),
+            _ => {
+                let color = state.side_to_move();
+                let mut best: Option<(String, i32)> = None;
+                for (placement, score) in placements.into_iter().take(LOOKAHEAD) {
+                    let mut next = state.clone();
+                    if !Self::place_state(&mut next, &placement) {
+                        continue;
+                    }
+                    let score = score - Self::reply_score(&mut next, opponent(color)) / 2;
+                    if best.as_ref().map(|b| score > b.1).unwrap_or(true) {
+                        best = Some((placement, score));
+                    }
+                }
+                best.map(|b| b.0)
+            }
+        }
+    }
+
+    /// Score of best placement for color. Zero if color does not place next
+    /// or has nothing left in hand.
+    fn reply_score(state: &mut P, color: Color) -> i32 {
+        if state.side_to_move() != color || state.get_hand(color, true).is_empty() {
+            return 0;
+        }
+        Self::placements(state).first().map(|p| p.1).unwrap_or(0)
+    }
+
+    /// Every placement for side to move, best first.
+    fn placements(state: &mut P) -> Vec<(String, i32)> {
+        let color = state.side_to_move();
+        let board = Self::board_view(state);
+        let hand = state.get_hand(color, true);
+        let mut seen = vec![];
+        let mut placements = vec![];
+        for c in hand.chars() {
+            if seen.contains(&c) {
+                continue;
+            }
+            seen.push(c);
+            let piece = match Piece::from_sfen(c) {
+                Some(piece) if piece.color == color => piece,
+                _ => continue,
+            };
+            let squares = match state.empty_squares(piece) {
+                Some(squares) => squares,
+                None => continue,
+            };
+            for sq in squares {
+                let name = sq.to_string();
+                if let Some(coords) = square_coords(&name) {
+                    let score = placement_score(&board, piece, coords);
+                    placements.push((format!("{}@{}", piece, name), score));
+                }
+            }
+        }
+        placements.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        placements
+    }
+
+    fn place_state(state: &mut P, placement: &str) -> bool {
+        match Move::from_sfen(placement) {
+            Some(Move::Put { to, piece, .. }) => state.place(piece, to).is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// Heuristic for placing piece: king safety, pawn structure and plinths.
+fn placement_score(board: &BoardView, piece: Piece, sq: (usize, usize)) -> i32 {
+    let color = piece.color;
+    let rank = board.relative_rank(sq.1, color) as i32;
+    let center = board.centrality(sq);
+    let neighbours = board.neighbours(sq);
+    let plinths = neighbours.iter().filter(|n| board.is_plinth(**n)).count() as i32;
+    let friends = neighbours
+        .iter()
+        .filter(|n| board.piece(**n).map(|p| p.color == color).unwrap_or(false))
+        .count() as i32;
+    let own_pawn_on_file = |file: usize| {
+        board
+            .pieces
+            .iter()
+            .any(|(s, p)| s.0 == file && p.color == color && p.piece_type == PieceType::Pawn)
+    };
+    let score = match piece.piece_type {
+        PieceType::King => {
+            let edge = sq.0 == 0 || sq.0 == board.size - 1;
+            1000 - rank * 20 + plinths * 5 - if edge { 10 } else { 0 }
+        }
+        PieceType::Pawn => {
+            let mut score = center * 2;
+            if let Some(king) = board.king(color) {
+                let king_rank = board.relative_rank(king.1, color) as i32;
+                if (sq.0 as i32 - king.0 as i32).abs() <= 1 && rank == king_rank + 1 {
+                    score += 30;
+                }
+            }
+            if own_pawn_on_file(sq.0) {
+                score -= 20;
+            }
+            if (sq.0 > 0 && own_pawn_on_file(sq.0 - 1)) || own_pawn_on_file(sq.0 + 1) {
+                score += 10;
+            }
+            score
+        }
+        PieceType::Knight => center * 3 + plinths * 8,
+        PieceType::Rook | PieceType::Chancellor => {
+            let open = if own_pawn_on_file(sq.0) { 0 } else { 15 };
+            open - plinths * 4
+        }
+        PieceType::Bishop | PieceType::Queen | PieceType::ArchBishop => center * 4 - plinths * 4,
+        _ => center * 3,
+    };
+    score + friends * 3
+}
+
+/// Small noise for weakest level, same seed gives same noise.
+fn jitter(placement: &str, seed: u64) -> i32 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for b in placement.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    (hash % 60) as i32
+}
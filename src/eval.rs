@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use shuuro::{
+    attacks::Attacks, bitboard::BitBoard, position::Position, Color, Piece, PieceType, Square,
+};
+use std::hash::Hash;
+
+use crate::board::{BoardView, Occupancy};
+use crate::local_position::LocalPosition;
+use crate::search::piece_values;
+
+/// Centipawns for each attacked square more than opponent.
+const MOBILITY: i32 = 4;
+
+/// Centipawns for each own piece next to king.
+const KING_SHELTER: i32 = 10;
+
+/// Centipawns for each enemy piece two squares or closer to king.
+const KING_ATTACKER: i32 = 15;
+
+/// Centipawns for being in check.
+const IN_CHECK: i32 = 30;
+
+/// Centipawns for knight on plinth or next to it.
+const PLINTH_KNIGHT: i32 = 15;
+
+/// Evaluation from white side, in centipawns. Score is sum of all parts.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Evaluation {
+    pub score: i32,
+    pub material: i32,
+    pub hand: i32,
+    pub mobility: i32,
+    pub king_safety: i32,
+    pub plinths: i32,
+}
+
+impl<S, B, A, P> LocalPosition<S, B, A, P>
+where
+    S: Square + Hash,
+    B: BitBoard<S>,
+    A: Attacks<S, B>,
+    P: Position<S, B, A> + Clone,
+{
+    /// Static evaluation of current position.
+    pub fn evaluate(&self) -> Evaluation {
+        let state = &self.state;
+        let values = centipawn_values(&piece_values(state.variant()));
+        let board = Self::board_view(state);
+        let occupancy = Self::occupancy(state);
+        let mut eval = Evaluation::default();
+        for color in [Color::White, Color::Black] {
+            let sign = if color == Color::White { 1 } else { -1 };
+            let pieces = board.pieces.iter().filter(|(_, p)| p.color == color);
+            for (sq, piece) in pieces {
+                eval.material += sign * values[piece.piece_type.index()];
+                if piece.piece_type == PieceType::Knight
+                    && (board.is_plinth(*sq)
+                        || board.neighbours(*sq).iter().any(|n| board.is_plinth(*n)))
+                {
+                    eval.plinths += sign * PLINTH_KNIGHT;
+                }
+            }
+            for c in state.get_hand(color, true).chars() {
+                if let Some(piece) = Piece::from_sfen(c) {
+                    eval.hand += sign * values[piece.piece_type.index()];
+                }
+            }
+            eval.mobility += sign * Self::mobility(&occupancy, color) * MOBILITY;
+            eval.king_safety += sign * king_safety(&board, color);
+            if board.king(color).is_some() && state.in_check(color) {
+                eval.king_safety -= sign * IN_CHECK;
+            }
+        }
+        eval.score = eval.material + eval.hand + eval.mobility + eval.king_safety + eval.plinths;
+        eval
+    }
+
+    /// Squares attacked by every piece, without squares of own pieces.
+    /// Attack maps are used for both sides, because only side to move has legal moves.
+    fn mobility(occupancy: &Occupancy<S>, color: Color) -> i32 {
+        occupancy
+            .pieces
+            .iter()
+            .filter(|(_, p)| p.color == color)
+            .map(|(sq, piece)| {
+                Self::piece_attacks(occupancy, *sq, piece)
+                    .iter()
+                    .filter(|to| {
+                        occupancy
+                            .pieces
+                            .get(*to)
+                            .map(|p| p.color != color)
+                            .unwrap_or(true)
+                    })
+                    .count() as i32
+            })
+            .sum()
+    }
+}
+
+/// Shop prices scaled so that pawn is 100.
+pub fn centipawn_values(values: &[i32; 10]) -> [i32; 10] {
+    let pawn = values[PieceType::Pawn.index()];
+    if pawn <= 0 {
+        return *values;
+    }
+    let mut scaled = [0; 10];
+    for (i, v) in values.iter().enumerate() {
+        scaled[i] = v * 100 / pawn;
+    }
+    scaled
+}
+
+fn king_safety(board: &BoardView, color: Color) -> i32 {
+    let king = match board.king(color) {
+        Some(king) => king,
+        None => return 0,
+    };
+    let shelter = board
+        .neighbours(king)
+        .iter()
+        .filter(|n| board.piece(**n).map(|p| p.color == color).unwrap_or(false))
+        .count() as i32;
+    let attackers = board
+        .pieces
+        .iter()
+        .filter(|(sq, p)| {
+            let distance = (sq.0 as i32 - king.0 as i32)
+                .abs()
+                .max((sq.1 as i32 - king.1 as i32).abs());
+            p.color != color && p.piece_type != PieceType::King && distance <= 2
+        })
+        .count() as i32;
+    shelter * KING_SHELTER - attackers * KING_ATTACKER
+}
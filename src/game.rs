@@ -0,0 +1,468 @@
+use serde::{Deserialize, Serialize};
+use shuuro::{
+    piece_type::PieceTypeIter, shuuro12::square12::Square12, Color, Move, Piece, PieceType, Variant,
+};
+use wasm_bindgen::prelude::*;
+
+use crate::clock::{ShuuroClock, TimeControl};
+use crate::position_container::PositionContainer;
+use crate::types::{ClockStateValue, ClockViewValue, OutcomeValue, TimeControlValue};
+
+/// Phase of the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+    Shop,
+    Deploy,
+    Fight,
+    Finished,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Shop => "shop",
+            Phase::Deploy => "deploy",
+            Phase::Fight => "fight",
+            Phase::Finished => "finished",
+        }
+    }
+}
+
+/// Reason why move was rejected by `ShuuroGame`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GameErrorKind {
+    WrongPhase,
+    AlreadyConfirmed,
+    IllegalMove,
+    GameOver,
+    InvalidSfen,
+    Timeout,
+}
+
+/// This represents game error thrown to JS.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameErrorJS {
+    pub kind: GameErrorKind,
+    pub message: String,
+}
+
+impl GameErrorJS {
+    pub fn new(kind: GameErrorKind, message: &str) -> Self {
+        Self {
+            kind,
+            message: String::from(message),
+        }
+    }
+
+    pub fn to_js(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self).unwrap_or_else(|_| JsValue::from_str(&self.message))
+    }
+}
+
+/// Class for whole game: shop, deploy and fight.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct ShuuroGame {
+    shop: shuuro::Selection<Square12>,
+    position: PositionContainer,
+    phase: Phase,
+    clock: Option<ShuuroClock>,
+}
+
+#[wasm_bindgen]
+impl ShuuroGame {
+    #[wasm_bindgen(constructor)]
+    pub fn new(variant: &str) -> Self {
+        let variant = Variant::from(&variant.to_string());
+        let mut shop = shuuro::Selection::default();
+        shop.update_variant(variant);
+        Self {
+            shop,
+            position: PositionContainer::new(variant),
+            phase: Phase::Shop,
+            clock: None,
+        }
+    }
+
+    /// Set starting board with plinths. Allowed only before deploy.
+    #[wasm_bindgen]
+    pub fn set_start_sfen(&mut self, sfen: &str) -> Result<(), JsValue> {
+        if self.phase != Phase::Shop {
+            return Err(wrong_phase("start position can be set only in shop").to_js());
+        }
+        self.position.set_sfen(sfen).map_err(|e| e.to_js())
+    }
+
+    /// Current phase: "shop", "deploy", "fight" or "finished".
+    #[wasm_bindgen]
+    pub fn phase(&self) -> String {
+        String::from(self.phase.as_str())
+    }
+
+    /// Side to move. In shop both players play, so it is "none".
+    #[wasm_bindgen]
+    pub fn side_to_move(&self) -> String {
+        match self.phase {
+            Phase::Shop | Phase::Finished => String::from("none"),
+            _ => self.position.side_to_move(),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn variant(&self) -> String {
+        self.position.variant()
+    }
+
+    /// Get credit for selected player.
+    #[wasm_bindgen]
+    pub fn get_credit(&self, s: char) -> i32 {
+        match Color::from_char(s) {
+            Some(Color::NoColor) | None => 0,
+            Some(c) => self.shop.credit(c),
+        }
+    }
+
+    /// Check if selected player is confirmed.
+    #[wasm_bindgen]
+    pub fn is_confirmed(&self, s: char) -> bool {
+        match Color::from_char(s) {
+            Some(Color::NoColor) | None => false,
+            Some(c) => self.shop.is_confirmed(c),
+        }
+    }
+
+    /// Get sfen for current position.
+    #[wasm_bindgen]
+    pub fn generate_sfen(&self) -> String {
+        self.position.generate_sfen()
+    }
+
+    /// Current outcome of fight.
+    #[wasm_bindgen]
+    pub fn outcome(&self) -> OutcomeValue {
+        self.position.outcome().to_js().unchecked_into()
+    }
+
+    /// Confirm players hand. When both players are confirmed, deploy starts.
+    #[wasm_bindgen]
+    pub fn confirm(&mut self, s: char) -> Result<(), JsValue> {
+        self.confirm_color(s).map_err(|e| e.to_js())
+    }
+
+    /// Play move for current phase: `+P` in shop, `P@sq` in deploy and board move in fight.
+    #[wasm_bindgen]
+    pub fn apply(&mut self, game_move: String) -> Result<(), JsValue> {
+        self.apply_move(game_move).map_err(|e| e.to_js())
+    }
+
+    /// CLOCK PART
+
+    /// Start clock for current phase. Time is in milliseconds, like `Date.now()`.
+    #[wasm_bindgen]
+    pub fn set_time_control(&mut self, control: TimeControlValue, now: f64) -> Result<(), JsValue> {
+        let control: TimeControl = serde_wasm_bindgen::from_value(control.into())?;
+        self.set_clock(control, now as u64);
+        Ok(())
+    }
+
+    /// Confirm hand and stop clock of that player.
+    #[wasm_bindgen]
+    pub fn confirm_at(&mut self, s: char, now: f64) -> Result<(), JsValue> {
+        self.confirm_color_at(s, now as u64).map_err(|e| e.to_js())
+    }
+
+    /// Play move and press clock. Throws `GameErrorJS` with kind "timeout" if time ran out.
+    #[wasm_bindgen]
+    pub fn apply_at(&mut self, game_move: String, now: f64) -> Result<(), JsValue> {
+        self.apply_move_at(game_move, now as u64)
+            .map_err(|e| e.to_js())
+    }
+
+    /// Color that ran out of time, "white" or "black". Game is finished then.
+    #[wasm_bindgen]
+    pub fn timeout(&mut self, now: f64) -> Option<String> {
+        self.timeout_at(now as u64)
+    }
+
+    /// Time left for both players, or null if there is no clock.
+    #[wasm_bindgen]
+    pub fn clock_at(&self, now: f64) -> ClockViewValue {
+        let view = self.clock.as_ref().map(|c| c.view(now as u64));
+        serde_wasm_bindgen::to_value(&view)
+            .unwrap_or(JsValue::NULL)
+            .unchecked_into()
+    }
+
+    /// Whole clock, so it can be saved with game and restored later.
+    #[wasm_bindgen]
+    pub fn clock_state(&self) -> ClockStateValue {
+        serde_wasm_bindgen::to_value(&self.clock)
+            .unwrap_or(JsValue::NULL)
+            .unchecked_into()
+    }
+
+    #[wasm_bindgen]
+    pub fn set_clock_state(&mut self, state: ClockStateValue) -> Result<(), JsValue> {
+        let clock: ShuuroClock = serde_wasm_bindgen::from_value(state.into())?;
+        self.restore_clock(clock);
+        Ok(())
+    }
+}
+
+impl ShuuroGame {
+    pub fn current_phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Native position used in deploy and fight.
+    pub fn position(&self) -> &PositionContainer {
+        &self.position
+    }
+
+    /// Forget undo history of position, so game is cheap to clone.
+    pub(crate) fn clear_undo(&mut self) {
+        self.position.clear_undo();
+    }
+
+    /// Set starting board without JS types.
+    pub fn set_start(&mut self, sfen: &str) -> Result<(), GameErrorJS> {
+        if self.phase != Phase::Shop {
+            return Err(wrong_phase("start position can be set only in shop"));
+        }
+        self.position
+            .set_sfen(sfen)
+            .map_err(|e| GameErrorJS::new(GameErrorKind::InvalidSfen, &e.message))
+    }
+
+    /// Start game from deploy or fight position, without shop.
+    pub fn set_position(&mut self, sfen: &str) -> Result<(), GameErrorJS> {
+        self.set_start(sfen)?;
+        self.phase = if self.position.count_hand_pieces().is_empty() {
+            Phase::Fight
+        } else {
+            Phase::Deploy
+        };
+        self.update_finished();
+        Ok(())
+    }
+
+    pub fn confirm_color(&mut self, s: char) -> Result<(), GameErrorJS> {
+        if self.phase != Phase::Shop {
+            return Err(wrong_phase("hands can be confirmed only in shop"));
+        }
+        let color = match Color::from_char(s) {
+            Some(Color::NoColor) | None => {
+                return Err(illegal_move("color must be 'w' or 'b'"));
+            }
+            Some(c) => c,
+        };
+        if self.shop.is_confirmed(color) {
+            return Err(GameErrorJS::new(
+                GameErrorKind::AlreadyConfirmed,
+                "player already confirmed hand",
+            ));
+        }
+        self.shop.confirm(color);
+        if self.shop.is_confirmed(Color::White) && self.shop.is_confirmed(Color::Black) {
+            let hand = self.confirmed_hand();
+            self.position.set_hand(&hand);
+            self.phase = Phase::Deploy;
+        }
+        Ok(())
+    }
+
+    /// Confirm hands of players that are not confirmed yet.
+    pub fn confirm_all(&mut self) -> Result<(), GameErrorJS> {
+        for color in ['w', 'b'] {
+            if !self.is_confirmed(color) {
+                self.confirm_color(color)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply_move(&mut self, game_move: String) -> Result<(), GameErrorJS> {
+        match self.phase {
+            Phase::Shop => self.buy(game_move),
+            Phase::Deploy => self.place(game_move),
+            Phase::Fight => self.fight(game_move),
+            Phase::Finished => Err(GameErrorJS::new(
+                GameErrorKind::GameOver,
+                "game is already finished",
+            )),
+        }
+    }
+
+    pub fn clock(&self) -> Option<&ShuuroClock> {
+        self.clock.as_ref()
+    }
+
+    pub fn set_clock(&mut self, control: TimeControl, now: u64) {
+        let mut clock = ShuuroClock::new(control);
+        clock.start_phase(self.phase, self.position_color(), now);
+        self.clock = Some(clock);
+    }
+
+    pub fn restore_clock(&mut self, clock: ShuuroClock) {
+        self.clock = Some(clock);
+    }
+
+    pub fn confirm_color_at(&mut self, s: char, now: u64) -> Result<(), GameErrorJS> {
+        self.check_time(now)?;
+        self.confirm_color(s)?;
+        match Color::from_char(s) {
+            Some(color) => self.press_clock(color, Phase::Shop, now),
+            None => Ok(()),
+        }
+    }
+
+    /// Time is checked before move. In shop clocks run until hand is confirmed.
+    pub fn apply_move_at(&mut self, game_move: String, now: u64) -> Result<(), GameErrorJS> {
+        self.check_time(now)?;
+        let phase = self.phase;
+        let mover = self.position_color();
+        self.apply_move(game_move)?;
+        if phase == Phase::Shop {
+            return Ok(());
+        }
+        self.press_clock(mover, phase, now)
+    }
+
+    /// Color that ran out of time. Game is finished then.
+    pub fn timeout_at(&mut self, now: u64) -> Option<String> {
+        let flagged = self.clock.as_mut()?.check_flag(now);
+        if flagged.is_some() {
+            self.phase = Phase::Finished;
+        }
+        flagged
+    }
+
+    fn check_time(&mut self, now: u64) -> Result<(), GameErrorJS> {
+        match self.timeout_at(now) {
+            Some(color) => Err(timeout(&color)),
+            None => Ok(()),
+        }
+    }
+
+    /// Stop clock of mover and start clock of next side.
+    /// New phase starts with its own budget.
+    fn press_clock(&mut self, mover: Color, phase: Phase, now: u64) -> Result<(), GameErrorJS> {
+        let side = self.position_color();
+        let current = self.phase;
+        let clock = match self.clock.as_mut() {
+            Some(clock) => clock,
+            None => return Ok(()),
+        };
+        if !clock.press(mover, now) {
+            clock.stop(now);
+            self.phase = Phase::Finished;
+            return Err(timeout(&clock.flagged.clone().unwrap_or_default()));
+        }
+        if current == Phase::Finished {
+            clock.stop(now);
+        } else if current != phase {
+            clock.start_phase(current, side, now);
+        } else if current != Phase::Shop {
+            clock.start(side, now);
+        }
+        Ok(())
+    }
+
+    fn position_color(&self) -> Color {
+        self.position
+            .side_to_move()
+            .chars()
+            .next()
+            .and_then(Color::from_char)
+            .unwrap_or(Color::NoColor)
+    }
+
+    fn buy(&mut self, game_move: String) -> Result<(), GameErrorJS> {
+        if !game_move.starts_with('+') {
+            return Err(wrong_phase("only purchases are allowed in shop"));
+        }
+        if let Some(Move::Select { piece }) = Move::<Square12>::from_sfen(&game_move) {
+            if self.shop.is_confirmed(piece.color) {
+                return Err(GameErrorJS::new(
+                    GameErrorKind::AlreadyConfirmed,
+                    "player already confirmed hand",
+                ));
+            }
+            let before = self.shop.get(piece);
+            self.shop.play(Move::Select { piece });
+            if self.shop.get(piece) > before {
+                return Ok(());
+            }
+        }
+        Err(illegal_move("piece can not be bought"))
+    }
+
+    fn place(&mut self, game_move: String) -> Result<(), GameErrorJS> {
+        if !game_move.contains('@') {
+            return Err(wrong_phase("only placements are allowed in deploy"));
+        }
+        if self.position.place(game_move).is_none() {
+            return Err(illegal_move("piece can not be placed there"));
+        }
+        if self.position.count_hand_pieces().is_empty() {
+            self.phase = Phase::Fight;
+            self.update_finished();
+        }
+        Ok(())
+    }
+
+    fn fight(&mut self, game_move: String) -> Result<(), GameErrorJS> {
+        if game_move.starts_with('+') || game_move.contains('@') {
+            return Err(wrong_phase("only board moves are allowed in fight"));
+        }
+        if self.position.make_move(game_move).is_none() {
+            return Err(illegal_move("move is not legal"));
+        }
+        self.update_finished();
+        Ok(())
+    }
+
+    fn update_finished(&mut self) {
+        if self.position.outcome().game_over {
+            self.phase = Phase::Finished;
+        }
+    }
+
+    /// Hand bought by both players in sfen format.
+    fn confirmed_hand(&self) -> String {
+        let mut hand = String::new();
+        for color in [Color::White, Color::Black] {
+            for piece_type in PieceTypeIter::default() {
+                if piece_type == PieceType::Plinth {
+                    continue;
+                }
+                let piece = Piece { piece_type, color };
+                let mut count = self.shop.get(piece);
+                if piece_type == PieceType::King {
+                    count = count.max(1);
+                }
+                for _ in 0..count {
+                    hand.push_str(&piece.to_string());
+                }
+            }
+        }
+        hand
+    }
+}
+
+fn wrong_phase(message: &str) -> GameErrorJS {
+    GameErrorJS::new(GameErrorKind::WrongPhase, message)
+}
+
+fn timeout(color: &str) -> GameErrorJS {
+    GameErrorJS::new(
+        GameErrorKind::Timeout,
+        &format!("{} ran out of time", color),
+    )
+}
+
+fn illegal_move(message: &str) -> GameErrorJS {
+    GameErrorJS::new(GameErrorKind::IllegalMove, message)
+}
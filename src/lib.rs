@@ -1,7 +1,29 @@
-mod local_position;
+pub mod analysis;
+mod board;
+pub mod buyer;
+pub mod clock;
+pub mod control;
+mod deploy;
+pub mod eval;
+pub mod game;
+pub mod local_position;
+pub mod notation;
+pub mod outcome;
 pub mod position;
-mod position_container;
+pub mod position_container;
+mod premove;
+pub mod promotion;
+pub mod record;
+pub mod replay;
+pub mod search;
 pub mod selection;
+pub mod sfen;
+pub mod types;
+mod utils;
+mod zobrist;
+
+pub use position_container::PositionContainer;
+pub use utils::set_panic_hook;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
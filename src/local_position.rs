@@ -1,14 +1,18 @@
-extern crate console_error_panic_hook;
-use js_sys::{Array, Map};
 use serde::{Deserialize, Serialize};
 use shuuro::{
-    attacks::Attacks, bitboard::BitBoard, position::Position, Color, Move, Piece, Square, Variant,
+    attacks::Attacks, bitboard::BitBoard, position::Position, Color, Move, Piece, PieceType,
+    Square, Variant,
 };
-use wasm_bindgen::JsValue;
 
-use std::{hash::Hash, marker::PhantomData, panic};
+use crate::outcome::{
+    MoveResultJS, OutcomeJS, OutcomeKind, RepetitionJS, RepetitionRule, MOVE_LIMIT,
+};
+use crate::promotion::split_promotion;
+use crate::sfen::{SfenErrorJS, SfenErrorKind};
+
+use std::{collections::HashMap, hash::Hash, marker::PhantomData};
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct LocalPosition<S, B, A, P>
 where
     S: Square + Hash,
@@ -20,7 +24,45 @@ where
     _b: PhantomData<B>,
     _a: PhantomData<A>,
     _p: PhantomData<P>,
+    pub(crate) state: P,
+    positions: Vec<PositionEntry>,
+    fight_start: usize,
+    halfmoves: u16,
+    repetition_rule: Option<RepetitionRule>,
+    history: Vec<HistoryEntry>,
+    undo_stack: Vec<Snapshot<P>>,
+    redo_stack: Vec<Snapshot<P>>,
+    pub(crate) premoves: Vec<String>,
+    pub(crate) hash: u64,
+}
+
+/// Move and sfen after it. Kept here, because sfen history of position
+/// starts again when promoted piece is changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct HistoryEntry {
+    game_move: String,
+    sfen: String,
+}
+
+/// Hash is checked first, sfen key confirms repetition in case of collision.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct PositionEntry {
+    hash: u64,
+    key: String,
+}
+
+/// Everything needed to restore previous position.
+/// Positions and history are only appended, so their length is enough to restore them.
+#[derive(Clone)]
+struct Snapshot<P> {
     state: P,
+    positions: usize,
+    history: usize,
+    last: Option<HistoryEntry>,
+    last_position: Option<PositionEntry>,
+    fight_start: usize,
+    halfmoves: u16,
+    hash: u64,
 }
 
 impl<S, B, A, P> LocalPosition<S, B, A, P>
@@ -28,30 +70,62 @@ where
     S: Square + Hash,
     B: BitBoard<S>,
     A: Attacks<S, B>,
-    P: Position<S, B, A>,
+    P: Position<S, B, A> + Clone,
 {
     pub fn new() -> Self {
         A::init();
+        let state = P::new();
+        let hash = Self::zobrist_of(&state);
         Self {
             _s: PhantomData,
             _b: PhantomData,
             _a: PhantomData,
             _p: PhantomData,
-            state: P::new(),
+            state,
+            positions: vec![],
+            fight_start: 0,
+            halfmoves: 0,
+            repetition_rule: None,
+            history: vec![],
+            undo_stack: vec![],
+            redo_stack: vec![],
+            premoves: vec![],
+            hash,
         }
     }
     // Main functions.
 
     pub fn change_variant(&mut self, variant: u8) {
         self.state.update_variant(Variant::from(variant));
+        self.hash = Self::zobrist_of(&self.state);
+        self.clear_undo();
+        self.history.clear();
     }
 
     pub fn set_hand(&mut self, s: &str) {
         self.state.set_hand(s);
+        self.hash = Self::zobrist_of(&self.state);
+        self.clear_undo();
+        self.history.clear();
     }
 
-    pub fn set_sfen(&mut self, s: &str) {
-        if let Err(_e) = self.state.set_sfen(s) {}
+    /// Set sfen. On error previous position is restored.
+    /// Board size is checked by `PositionContainer`.
+    pub fn set_sfen(&mut self, s: &str) -> Result<(), SfenErrorJS> {
+        let previous = self.state.clone();
+        if self.state.set_sfen(s).is_err() {
+            self.state = previous;
+            return Err(SfenErrorJS::new(
+                SfenErrorKind::IllegalBoardState,
+                "sfen describes illegal position",
+            ));
+        }
+        self.hash = Self::zobrist_of(&self.state);
+        self.clear_undo();
+        self.history.clear();
+        self.positions.clear();
+        self.reset_fight_tracking();
+        Ok(())
     }
 
     pub fn generate_sfen(&self) -> String {
@@ -66,25 +140,15 @@ where
         self.state.variant().start_credit()
     }
 
-    pub fn map_plinths(&self) -> Map {
-        let list = Map::new();
+    /// Squares with plinths.
+    pub fn map_plinths(&self) -> Vec<String> {
         let bb = self.state.player_bb(Color::NoColor);
-        for i in bb {
-            let example = PieceJS {
-                role: String::from("l-piece"),
-                color: String::from("white"),
-            };
-            let sq = i.to_string();
-            if let Ok(piece) = serde_wasm_bindgen::to_value(&example) {
-                list.set(&JsValue::from_str(sq.as_str()), &piece);
-            }
-        }
-
-        list
+        bb.into_iter().map(|sq| sq.to_string()).collect()
     }
 
-    pub fn map_pieces(&self) -> Map {
-        let list = Map::new();
+    /// All pieces on board by square.
+    pub fn map_pieces(&self) -> HashMap<String, PieceJS> {
+        let mut list = HashMap::new();
         let colors = [Color::White, Color::Black];
         for i in colors {
             let bb = self.state.player_bb(i);
@@ -92,16 +156,13 @@ where
             for sq in bb {
                 let piece = self.state.piece_at(sq);
                 if let Some(piece) = piece {
-                    let sq = sq.to_string();
                     let mut role = piece.to_string().to_lowercase();
                     role.push_str("-piece");
                     let p = PieceJS {
                         role,
                         color: String::from(color),
                     };
-                    if let Ok(piece) = serde_wasm_bindgen::to_value(&p) {
-                        list.set(&JsValue::from_str(sq.as_str()), &piece);
-                    }
+                    list.insert(sq.to_string(), p);
                 }
             }
         }
@@ -115,15 +176,241 @@ where
         sum
     }
 
+    /// Last move played here, or last move from sfen history of position.
     pub fn last_move(&self) -> String {
-        let history = self.state.get_sfen_history();
-        history.first().2
+        match self.history.last() {
+            Some(entry) => entry.game_move.clone(),
+            None => self.state.get_sfen_history().first().2,
+        }
+    }
+
+    /// Sfen after every move since position was set.
+    pub fn sfen_history(&self) -> Vec<String> {
+        self.history.iter().map(|h| h.sfen.clone()).collect()
+    }
+
+    fn push_history(&mut self, game_move: String) {
+        let sfen = self.state.generate_sfen();
+        self.history.push(HistoryEntry { game_move, sfen });
     }
 
     pub fn is_check(&self) -> bool {
         self.state.in_check(self.state.side_to_move())
     }
 
+    /// Current state of the game.
+    pub fn outcome(&self) -> OutcomeJS {
+        if !self.count_hand_pieces().is_empty() {
+            return OutcomeJS::new(OutcomeKind::Ongoing, None);
+        }
+        let stm = self.state.side_to_move();
+        let check = self.state.in_check(stm);
+        let no_moves = self
+            .state
+            .legal_moves(stm)
+            .into_iter()
+            .all(|m| m.1.count() == 0);
+        if no_moves {
+            if check {
+                let winner = self.get_color(opponent(stm));
+                return OutcomeJS::new(OutcomeKind::Checkmate, Some(winner));
+            }
+            return OutcomeJS::new(OutcomeKind::Stalemate, None);
+        }
+        if self.insufficient_material() {
+            return OutcomeJS::new(OutcomeKind::InsufficientMaterial, None);
+        }
+        if self.repetition().automatic {
+            return OutcomeJS::new(OutcomeKind::Repetition, None);
+        }
+        if self.halfmoves >= MOVE_LIMIT {
+            return OutcomeJS::new(OutcomeKind::MoveLimit, None);
+        }
+        if check {
+            return OutcomeJS::new(OutcomeKind::Check, None);
+        }
+        OutcomeJS::new(OutcomeKind::Ongoing, None)
+    }
+
+    /// How many times current position appeared in fight.
+    pub fn repetitions(&self) -> usize {
+        let fight = self.fight_positions();
+        match fight.last() {
+            Some(current) => fight
+                .iter()
+                .filter(|p| p.hash == current.hash && p.key == current.key)
+                .count(),
+            None => 0,
+        }
+    }
+
+    /// Repetition count with draw flags from rule of variant.
+    pub fn repetition(&self) -> RepetitionJS {
+        let count = self.repetitions();
+        let rule = self.repetition_rule();
+        let reached = |limit: usize| limit > 0 && count >= limit;
+        RepetitionJS {
+            count,
+            claimable: reached(rule.claim),
+            automatic: reached(rule.automatic),
+        }
+    }
+
+    pub fn repetition_rule(&self) -> RepetitionRule {
+        self.repetition_rule
+            .unwrap_or_else(|| RepetitionRule::for_variant(self.state.variant()))
+    }
+
+    /// Change rule for this position. Default rule depends on variant.
+    pub fn set_repetition_rule(&mut self, rule: RepetitionRule) {
+        self.repetition_rule = Some(rule);
+    }
+
+    /// Hashes of positions since fight started, current one is last.
+    pub(crate) fn fight_hashes(&self) -> Vec<u64> {
+        self.fight_positions().iter().map(|p| p.hash).collect()
+    }
+
+    fn fight_positions(&self) -> &[PositionEntry] {
+        &self.positions[self.fight_start..]
+    }
+
+    /// Sfen without ply counter.
+    fn position_key(sfen: &str) -> String {
+        match sfen.rsplit_once(' ') {
+            Some((key, _)) => String::from(key),
+            None => String::from(sfen),
+        }
+    }
+
+    fn push_position(&mut self, key: String) {
+        let hash = self.hash;
+        self.positions.push(PositionEntry { hash, key });
+    }
+
+    /// Halfmoves since last capture or pawn move.
+    pub(crate) fn halfmoves(&self) -> u16 {
+        self.halfmoves
+    }
+
+    /// Only kings, or kings with one bishop or knight are left.
+    fn insufficient_material(&self) -> bool {
+        let mut minors = 0;
+        for color in [Color::White, Color::Black] {
+            for sq in self.state.player_bb(color) {
+                if let Some(piece) = self.state.piece_at(sq) {
+                    match piece.piece_type {
+                        PieceType::King => (),
+                        PieceType::Bishop | PieceType::Knight => minors += 1,
+                        _ => return false,
+                    }
+                }
+            }
+        }
+        minors <= 1
+    }
+
+    fn pawn_squares(&self, color: Color) -> Vec<String> {
+        let mut squares = vec![];
+        for sq in self.state.player_bb(color) {
+            if let Some(piece) = self.state.piece_at(sq) {
+                if piece.piece_type == PieceType::Pawn {
+                    squares.push(sq.to_string());
+                }
+            }
+        }
+        squares
+    }
+
+    /// Start counting repetitions from current position.
+    /// Older positions are kept for undo.
+    fn reset_fight_tracking(&mut self) {
+        self.fight_start = self.positions.len();
+        let key = Self::position_key(&self.state.generate_sfen());
+        self.push_position(key);
+        self.halfmoves = 0;
+        self.premoves.clear();
+    }
+
+    // Undo part
+
+    /// Take back last move or placement.
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(previous) => {
+                let current = self.snapshot();
+                self.redo_stack.push(current);
+                self.restore(previous);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Play again move that was taken back.
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(next) => {
+                let current = self.snapshot();
+                self.undo_stack.push(current);
+                self.restore(next);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    fn snapshot(&self) -> Snapshot<P> {
+        Snapshot {
+            state: self.state.clone(),
+            positions: self.positions.len(),
+            history: self.history.len(),
+            last: self.history.last().cloned(),
+            last_position: self.positions.last().cloned(),
+            fight_start: self.fight_start,
+            halfmoves: self.halfmoves,
+            hash: self.hash,
+        }
+    }
+
+    /// Undo drops positions and history after snapshot. Redo is always one move
+    /// ahead, so only its own position and history entry are pushed back.
+    fn restore(&mut self, snapshot: Snapshot<P>) {
+        self.state = snapshot.state;
+        self.positions.truncate(snapshot.positions);
+        if self.positions.len() < snapshot.positions {
+            self.positions.extend(snapshot.last_position);
+        }
+        self.history.truncate(snapshot.history);
+        if self.history.len() < snapshot.history {
+            self.history.extend(snapshot.last);
+        }
+        self.fight_start = snapshot.fight_start;
+        self.halfmoves = snapshot.halfmoves;
+        self.hash = snapshot.hash;
+        self.premoves.clear();
+    }
+
+    /// New move was played, so redo history is lost.
+    fn push_undo(&mut self, snapshot: Snapshot<P>) {
+        self.undo_stack.push(snapshot);
+        self.redo_stack.clear();
+    }
+
+    /// Forget moves that can be taken back or played again.
+    pub fn clear_undo(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
     fn get_color(&self, c: Color) -> &str {
         if c == Color::White {
             return "white";
@@ -135,20 +422,15 @@ where
 
     // Deploy part
 
-    pub fn place_moves(&mut self, piece: char) -> Map {
-        let map = Map::new();
+    /// Squares where piece can be placed, keyed by `P@`.
+    pub fn place_moves(&mut self, piece: char) -> HashMap<String, Vec<String>> {
+        let mut map = HashMap::new();
         if let Some(p) = Piece::from_sfen(piece) {
             let bb = self.state.empty_squares(p).unwrap_or_default();
-            let moves = Array::new();
-            for i in bb {
-                moves.push(&JsValue::from_str(&i.to_string()));
-            }
-            let mut piece = piece.to_uppercase().to_string();
-            piece.push('@');
-            let key = piece;
-            let key = JsValue::from_str(&key);
-            let value = JsValue::from(moves);
-            map.set(&key, &value);
+            let moves = bb.into_iter().map(|sq| sq.to_string()).collect();
+            let mut key = piece.to_uppercase().to_string();
+            key.push('@');
+            map.insert(key, moves);
         }
         map
     }
@@ -165,45 +447,191 @@ where
         #[allow(clippy::collapsible_match)]
         if let Some(m) = m {
             if let Move::Put { to, piece, .. } = m {
-                return self.state.place(piece, to);
+                let previous = self.snapshot();
+                let placed = self.state.place(piece, to);
+                if let Some(placed) = &placed {
+                    self.hash ^= Self::zobrist_place_delta(&previous.state, &self.state, piece, to);
+                    self.push_undo(previous);
+                    self.reset_fight_tracking();
+                    self.push_history(placed.clone());
+                }
+                return placed;
             }
         }
         None
     }
 
-    pub fn legal_moves(&self, color: Color) -> Map {
-        panic::set_hook(Box::new(console_error_panic_hook::hook));
-        let map = Map::new();
+    /// Destinations for every piece of side to move.
+    pub fn legal_moves(&self, color: Color) -> HashMap<String, Vec<String>> {
+        let mut map = HashMap::new();
         let stm = self.state.side_to_move();
         if color == stm {
             let l_m = self.state.legal_moves(stm);
             for m in l_m {
-                let piece = m.0.to_string();
-                let moves = Array::new();
-                for sq in m.1 {
-                    let value = JsValue::from_str(&sq.to_string());
-                    moves.push(&value);
-                }
-                let piece = JsValue::from_str(&piece);
-                let moves = JsValue::from(moves);
-                map.set(&piece, &moves);
+                let moves = m.1.into_iter().map(|sq| sq.to_string()).collect();
+                map.insert(m.0.to_string(), moves);
             }
         }
         map
     }
 
-    pub fn make_move(&mut self, game_move: String) -> Option<String> {
-        #[allow(clippy::collapsible_match)]
-        let output = self.state.play(&game_move);
-        match output {
-            Ok(_) => Some(self.state.get_sfen_history().first().2),
-            Err(_) => None,
+    /// Destinations of piece on square, if it belongs to side to move.
+    /// Moves come from same generator as `legal_moves`, but only this piece
+    /// is converted to square names.
+    pub fn legal_moves_from(&self, square: &str) -> Vec<String> {
+        let stm = self.state.side_to_move();
+        self.state
+            .legal_moves(stm)
+            .into_iter()
+            .find(|m| m.0.to_string() == square)
+            .map(|m| m.1.into_iter().map(|sq| sq.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Check fight move or placement in sfen format without building moves map.
+    pub fn is_legal(&self, game_move: &str) -> bool {
+        if game_move.contains('@') {
+            return self.parse_move(game_move).as_deref() == Ok(game_move);
         }
+        if let (fight, Some(piece)) = split_promotion(game_move) {
+            return self.valid_promotion(fight, piece);
+        }
+        let (from, to) = match game_move.split_once('_') {
+            Some(squares) => squares,
+            None => return false,
+        };
+        self.legal_moves_from(from).iter().any(|sq| sq == to)
+    }
+
+    /// All legal moves for side to move in sfen format, sorted.
+    pub fn fight_moves(&self) -> Vec<String> {
+        Self::moves_of(&self.state)
+    }
+
+    pub(crate) fn moves_of(state: &P) -> Vec<String> {
+        let stm = state.side_to_move();
+        let mut moves = vec![];
+        for m in state.legal_moves(stm) {
+            let from = m.0.to_string();
+            for to in m.1 {
+                moves.push(fight_move(&from, &to.to_string()));
+            }
+        }
+        moves.sort();
+        moves
+    }
+
+    // Perft part
+
+    /// Count leaf nodes of move tree.
+    pub fn perft(&self, depth: u8) -> u64 {
+        Self::perft_state(&self.state, depth)
+    }
+
+    /// Leaf nodes for every root move.
+    pub fn divide(&self, depth: u8) -> Vec<(String, u64)> {
+        if depth == 0 {
+            return vec![];
+        }
+        Self::moves_of(&self.state)
+            .into_iter()
+            .map(|m| {
+                let mut next = self.state.clone();
+                let nodes = match next.play(&m) {
+                    Ok(_) => Self::perft_state(&next, depth - 1),
+                    Err(_) => 0,
+                };
+                (m, nodes)
+            })
+            .collect()
+    }
+
+    fn perft_state(state: &P, depth: u8) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = Self::moves_of(state);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let mut nodes = 0;
+        for m in moves {
+            let mut next = state.clone();
+            if next.play(&m).is_ok() {
+                nodes += Self::perft_state(&next, depth - 1);
+            }
+        }
+        nodes
+    }
+
+    /// Play move, then first premove of other side if there is one.
+    pub fn make_move(&mut self, game_move: String) -> Option<MoveResultJS> {
+        let mut result = self.play_move(&game_move)?;
+        result.premove = self.play_premove();
+        Some(result)
+    }
+
+    /// Play fight move. Promotion piece can be chosen with `=`, like `e7_e8=N`.
+    pub(crate) fn play_move(&mut self, game_move: &str) -> Option<MoveResultJS> {
+        let (fight, promotion) = split_promotion(game_move);
+        if let Some(piece) = promotion {
+            if !self.valid_promotion(fight, piece) {
+                return None;
+            }
+        }
+        let mover = self.state.side_to_move();
+        let enemies = self.state.player_bb(opponent(mover)).count();
+        let pawns = self.pawn_squares(mover);
+        let previous = self.snapshot();
+        if self.state.play(fight).is_err() {
+            return None;
+        }
+        let mut last_move = self.state.get_sfen_history().first().2;
+        if let (Some(piece), Some((_, to))) = (promotion, fight.split_once('_')) {
+            if !Self::set_promoted(&mut self.state, to, piece, mover) {
+                self.state = previous.state;
+                return None;
+            }
+            last_move = format!("{}={}", fight, piece.to_ascii_uppercase());
+        }
+        if let Some(Move::Normal { from, to, .. }) = Move::<S>::from_sfen(fight) {
+            self.hash ^= Self::zobrist_delta(&previous.state, &self.state, &[from, to]);
+        }
+        self.push_undo(previous);
+        self.push_history(last_move.clone());
+        let captured = self.state.player_bb(opponent(mover)).count() < enemies;
+        if captured || self.pawn_squares(mover) != pawns {
+            self.halfmoves = 0;
+        } else {
+            self.halfmoves += 1;
+        }
+        if let Some(entry) = self.history.last() {
+            let key = Self::position_key(&entry.sfen);
+            self.push_position(key);
+        }
+        Some(MoveResultJS {
+            game_move: last_move,
+            outcome: self.outcome(),
+            premove: None,
+        })
+    }
+}
+
+/// Fight move in sfen format.
+pub fn fight_move(from: &str, to: &str) -> String {
+    format!("{}_{}", from, to)
+}
+
+pub(crate) fn opponent(c: Color) -> Color {
+    match c {
+        Color::White => Color::Black,
+        Color::Black => Color::White,
+        _ => Color::NoColor,
     }
 }
 
 /// This represents piece.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PieceJS {
     pub role: String,
     pub color: String,
@@ -0,0 +1,389 @@
+use serde::{Deserialize, Serialize};
+use shuuro::{
+    attacks::Attacks, bitboard::BitBoard, position::Position, Color, Piece, PieceType, Square,
+};
+use std::hash::Hash;
+use wasm_bindgen::JsValue;
+
+use crate::local_position::{fight_move, opponent, LocalPosition};
+use crate::promotion::split_promotion;
+use crate::sfen::square_coords;
+
+/// Reason why move text was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MoveParseErrorKind {
+    Syntax,
+    Illegal,
+    Ambiguous,
+}
+
+/// This represents move parse error thrown to JS.
+/// Candidates are moves that match ambiguous text.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveParseErrorJS {
+    pub kind: MoveParseErrorKind,
+    pub message: String,
+    pub candidates: Vec<String>,
+}
+
+impl MoveParseErrorJS {
+    pub fn new(kind: MoveParseErrorKind, message: &str) -> Self {
+        Self {
+            kind,
+            message: String::from(message),
+            candidates: vec![],
+        }
+    }
+
+    pub fn to_js(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self).unwrap_or_else(|_| JsValue::from_str(&self.message))
+    }
+}
+
+/// Legal board move with moving piece.
+pub(crate) struct LegalMove {
+    pub from: String,
+    pub to: String,
+    pub piece: Piece,
+    pub capture: bool,
+}
+
+impl<S, B, A, P> LocalPosition<S, B, A, P>
+where
+    S: Square + Hash,
+    B: BitBoard<S>,
+    A: Attacks<S, B>,
+    P: Position<S, B, A> + Clone,
+{
+    /// Move in SAN-like notation: `Nf3`, `exd5`, `Qh4#`, `N@e4` or `+N`.
+    /// Move must be legal in current position.
+    pub fn to_san(&self, game_move: &str, figurine: bool) -> Option<String> {
+        if let Some(piece) = game_move.strip_prefix('+') {
+            let piece = Piece::from_sfen(piece.chars().next()?)?;
+            return Some(format!("+{}", piece_letter(&piece, figurine)));
+        }
+        if let Some((piece, to)) = game_move.split_once('@') {
+            let piece = Piece::from_sfen(piece.chars().next()?)?;
+            return Some(format!("{}@{}", piece_letter(&piece, figurine), to));
+        }
+        let (game_move, promotion) = split_promotion(game_move);
+        if let Some(piece) = promotion {
+            if !self.valid_promotion(game_move, piece) {
+                return None;
+            }
+        }
+        let moves = Self::legal_list(&self.state);
+        self.san_in(game_move, promotion, &moves, figurine)
+    }
+
+    /// SAN of board move, with disambiguation taken from legal moves of position.
+    /// Legal moves are generated once by caller, so many moves can share them.
+    /// Chosen promotion piece, if any, is given apart from move.
+    pub(crate) fn san_in(
+        &self,
+        game_move: &str,
+        promotion: Option<char>,
+        moves: &[LegalMove],
+        figurine: bool,
+    ) -> Option<String> {
+        let (from, to) = game_move.split_once('_')?;
+        let to = &to[..to
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(to.len())];
+        let state = &self.state;
+        let stm = state.side_to_move();
+        let m = moves.iter().find(|m| m.from == from && m.to == to)?;
+        let (piece, capture) = (m.piece, m.capture);
+        let rivals: Vec<(String, PieceType)> = moves
+            .iter()
+            .filter(|m| m.to == to && m.from != from)
+            .map(|m| (m.from.clone(), m.piece.piece_type))
+            .collect();
+
+        let mut san = String::new();
+        if piece.piece_type == PieceType::Pawn {
+            if capture {
+                san.push_str(&from[..1]);
+            }
+        } else {
+            san.push_str(&piece_letter(&piece, figurine));
+            san.push_str(&disambiguation(from, &rivals, piece.piece_type));
+        }
+        if capture {
+            san.push('x');
+        }
+        san.push_str(to);
+
+        let mut next = state.clone();
+        next.play(game_move).ok()?;
+        if let Some(piece) = promotion {
+            if !Self::set_promoted(&mut next, to, piece, stm) {
+                return None;
+            }
+        }
+        if let Some(promoted) = Self::piece_on(&next, to, stm) {
+            if promoted.piece_type != piece.piece_type {
+                san.push('=');
+                san.push_str(&piece_letter(&promoted, figurine));
+            }
+        }
+        let them = next.side_to_move();
+        if next.in_check(them) {
+            let mated = next.legal_moves(them).into_iter().all(|m| m.1.count() == 0);
+            san.push(if mated { '#' } else { '+' });
+        }
+        Some(san)
+    }
+
+    /// Every legal move of side to move.
+    pub(crate) fn legal_list(state: &P) -> Vec<LegalMove> {
+        let stm = state.side_to_move();
+        let mut moves = vec![];
+        for m in state.legal_moves(stm) {
+            let piece = match state.piece_at(m.0) {
+                Some(p) => Piece {
+                    piece_type: p.piece_type,
+                    color: p.color,
+                },
+                None => continue,
+            };
+            let from = m.0.to_string();
+            for to in m.1 {
+                let capture = state
+                    .piece_at(to)
+                    .map(|p| p.color == opponent(stm))
+                    .unwrap_or(false);
+                moves.push(LegalMove {
+                    from: from.clone(),
+                    to: to.to_string(),
+                    piece,
+                    capture,
+                });
+            }
+        }
+        moves
+    }
+
+    /// Move in sfen format from text like `Nf3`, `exd5`, `Q@c10` or `g1f3`.
+    /// Promotion piece after `=` is kept, like `e7_e8=N`.
+    pub fn parse_move(&self, input: &str) -> Result<String, MoveParseErrorJS> {
+        let text = input.trim().trim_end_matches(['+', '#', '!', '?']);
+        let (text, promotion) = split_promotion(text);
+        let text: String = text
+            .chars()
+            .filter(|c| !matches!(c, 'x' | ':' | '-' | '_'))
+            .collect();
+        if text.is_empty() {
+            return Err(MoveParseErrorJS::new(
+                MoveParseErrorKind::Syntax,
+                "move is empty",
+            ));
+        }
+        match text.split_once('@') {
+            Some((piece, to)) => self.parse_placement(piece, to),
+            None => {
+                let game_move = self.parse_fight_move(&text)?;
+                match promotion {
+                    Some(piece) if self.valid_promotion(&game_move, piece) => {
+                        Ok(format!("{}={}", game_move, piece.to_ascii_uppercase()))
+                    }
+                    Some(piece) => Err(MoveParseErrorJS::new(
+                        MoveParseErrorKind::Illegal,
+                        &format!("'{}' can not promote to '{}'", game_move, piece),
+                    )),
+                    None => Ok(game_move),
+                }
+            }
+        }
+    }
+
+    fn parse_placement(&self, piece: &str, to: &str) -> Result<String, MoveParseErrorJS> {
+        let stm = self.state.side_to_move();
+        let mut chars = piece.chars();
+        let c = match (chars.next(), chars.next()) {
+            (Some(c), None) if stm == Color::White => c.to_ascii_uppercase(),
+            (Some(c), None) => c.to_ascii_lowercase(),
+            _ => {
+                return Err(MoveParseErrorJS::new(
+                    MoveParseErrorKind::Syntax,
+                    "placement must have one piece letter",
+                ))
+            }
+        };
+        let piece = match Piece::from_sfen(c) {
+            Some(piece) if piece.piece_type != PieceType::Plinth && square_coords(to).is_some() => {
+                piece
+            }
+            _ => {
+                return Err(MoveParseErrorJS::new(
+                    MoveParseErrorKind::Syntax,
+                    &format!("'{}@{}' is not a placement", c, to),
+                ))
+            }
+        };
+        if !self.state.get_hand(stm, true).contains(c) {
+            return Err(MoveParseErrorJS::new(
+                MoveParseErrorKind::Illegal,
+                &format!("there is no '{}' in hand", c),
+            ));
+        }
+        let mut state = self.state.clone();
+        let empty = state.empty_squares(piece).unwrap_or_default();
+        if !empty.into_iter().any(|sq| sq.to_string() == to) {
+            return Err(MoveParseErrorJS::new(
+                MoveParseErrorKind::Illegal,
+                &format!("'{}' can not be placed on {}", c, to),
+            ));
+        }
+        Ok(format!("{}@{}", c, to))
+    }
+
+    fn parse_fight_move(&self, text: &str) -> Result<String, MoveParseErrorJS> {
+        let syntax = || {
+            MoveParseErrorJS::new(
+                MoveParseErrorKind::Syntax,
+                &format!("'{}' is not a move", text),
+            )
+        };
+        let split = text
+            .rfind(|c: char| c.is_ascii_lowercase())
+            .ok_or_else(syntax)?;
+        let (prefix, to) = text.split_at(split);
+        square_coords(to).ok_or_else(syntax)?;
+
+        let mut rest = prefix;
+        let mut piece_type = None;
+        if let Some(c) = prefix.chars().next().filter(|c| c.is_ascii_uppercase()) {
+            let piece = Piece::from_sfen(c).ok_or_else(syntax)?;
+            piece_type = Some(piece.piece_type);
+            rest = &prefix[1..];
+        }
+        let file = rest.chars().next().filter(|c| c.is_ascii_lowercase());
+        let rank = &rest[file.map(|_| 1).unwrap_or(0)..];
+        if !rank.chars().all(|c| c.is_ascii_digit()) {
+            return Err(syntax());
+        }
+        if piece_type.is_none() && (file.is_none() || rank.is_empty()) {
+            piece_type = Some(PieceType::Pawn);
+        }
+
+        let state = &self.state;
+        let mut candidates = vec![];
+        for m in state.legal_moves(state.side_to_move()) {
+            let from = m.0.to_string();
+            let moved = state.piece_at(m.0).map(|p| p.piece_type);
+            if piece_type.is_some() && moved != piece_type {
+                continue;
+            }
+            if file.map(|f| !from.starts_with(f)).unwrap_or(false)
+                || (!rank.is_empty() && &from[1..] != rank)
+            {
+                continue;
+            }
+            if m.1.into_iter().any(|sq| sq.to_string() == to) {
+                candidates.push(fight_move(&from, to));
+            }
+        }
+        match candidates.len() {
+            0 => Err(MoveParseErrorJS::new(
+                MoveParseErrorKind::Illegal,
+                &format!("no legal move matches '{}'", text),
+            )),
+            1 => Ok(candidates.remove(0)),
+            _ => {
+                candidates.sort();
+                let mut error = MoveParseErrorJS::new(
+                    MoveParseErrorKind::Ambiguous,
+                    &format!("'{}' matches {} moves", text, candidates.len()),
+                );
+                error.candidates = candidates;
+                Err(error)
+            }
+        }
+    }
+
+    /// Legal moves in SAN, coordinate and placement form that start with prefix.
+    pub fn complete_move(&self, prefix: &str) -> Vec<String> {
+        let mut list = vec![];
+        let moves = Self::legal_list(&self.state);
+        for m in moves.iter() {
+            let game_move = fight_move(&m.from, &m.to);
+            if let Some(san) = self.san_in(&game_move, None, &moves, false) {
+                list.push(san);
+            }
+            list.push(format!("{}{}", m.from, m.to));
+        }
+        let stm = self.state.side_to_move();
+        let mut state = self.state.clone();
+        let mut seen = vec![];
+        for c in state.get_hand(stm, true).chars() {
+            if seen.contains(&c) {
+                continue;
+            }
+            seen.push(c);
+            if let Some(piece) = Piece::from_sfen(c) {
+                let empty = state.empty_squares(piece).unwrap_or_default();
+                for sq in empty {
+                    list.push(format!("{}@{}", c.to_ascii_uppercase(), sq));
+                }
+            }
+        }
+        list.retain(|m| m.starts_with(prefix));
+        list.sort();
+        list.dedup();
+        list
+    }
+
+    fn piece_on(state: &P, square: &str, color: Color) -> Option<Piece> {
+        for sq in state.player_bb(color) {
+            if sq.to_string() == square {
+                if let Some(p) = state.piece_at(sq) {
+                    return Some(Piece {
+                        piece_type: p.piece_type,
+                        color: p.color,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// File, rank or whole square when other pieces of same type can reach same square.
+fn disambiguation(from: &str, rivals: &[(String, PieceType)], piece_type: PieceType) -> String {
+    let rivals: Vec<&String> = rivals
+        .iter()
+        .filter(|r| r.1 == piece_type)
+        .map(|r| &r.0)
+        .collect();
+    if rivals.is_empty() {
+        return String::new();
+    }
+    let (file, rank) = from.split_at(1);
+    if rivals.iter().all(|r| !r.starts_with(file)) {
+        String::from(file)
+    } else if rivals.iter().all(|r| &r[1..] != rank) {
+        String::from(rank)
+    } else {
+        String::from(from)
+    }
+}
+
+/// Uppercase letter or figurine for piece.
+pub fn piece_letter(piece: &Piece, figurine: bool) -> String {
+    let letter = piece.to_string().to_uppercase();
+    if !figurine {
+        return letter;
+    }
+    let figure = match piece.piece_type {
+        PieceType::King => "♔",
+        PieceType::Queen => "♕",
+        PieceType::Rook => "♖",
+        PieceType::Bishop => "♗",
+        PieceType::Knight => "♘",
+        PieceType::Pawn => "♙",
+        _ => return letter,
+    };
+    String::from(figure)
+}
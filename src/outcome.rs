@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use shuuro::Variant;
+use wasm_bindgen::JsValue;
+
+/// Halfmoves without capture or pawn move before game is drawn.
+pub const MOVE_LIMIT: u16 = 100;
+
+/// How many times same position must appear for draw.
+pub const REPETITION_LIMIT: usize = 3;
+
+/// Repetitions before automatic draw in chess variants, where threefold must be claimed.
+pub const AUTOMATIC_REPETITION_LIMIT: usize = 5;
+
+/// State of the game after last move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutcomeKind {
+    Ongoing,
+    Check,
+    Checkmate,
+    Stalemate,
+    Repetition,
+    MoveLimit,
+    InsufficientMaterial,
+}
+
+/// This represents outcome.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutcomeJS {
+    pub kind: OutcomeKind,
+    pub game_over: bool,
+    pub winner: Option<String>,
+}
+
+impl OutcomeJS {
+    pub fn new(kind: OutcomeKind, winner: Option<&str>) -> Self {
+        let game_over = !matches!(kind, OutcomeKind::Ongoing | OutcomeKind::Check);
+        Self {
+            kind,
+            game_over,
+            winner: winner.map(String::from),
+        }
+    }
+
+    pub fn to_js(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self).unwrap_or(JsValue::NULL)
+    }
+}
+
+/// This represents result of `make_move`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveResultJS {
+    pub game_move: String,
+    pub outcome: OutcomeJS,
+    pub premove: Option<PremoveJS>,
+}
+
+impl MoveResultJS {
+    pub fn to_js(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self).unwrap_or(JsValue::NULL)
+    }
+}
+
+/// First queued premove, checked after opponent's move.
+/// When it is not legal, whole queue is cancelled.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PremoveJS {
+    pub game_move: String,
+    pub played: bool,
+    pub outcome: Option<OutcomeJS>,
+}
+
+/// How many repetitions of position are needed for draw.
+/// Zero turns that part of rule off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepetitionRule {
+    pub claim: usize,
+    pub automatic: usize,
+}
+
+impl RepetitionRule {
+    /// Chess variants follow chess rules: threefold can be claimed, fivefold is draw.
+    /// In Shuuro threefold is draw without claim.
+    pub fn for_variant(variant: Variant) -> Self {
+        match variant {
+            Variant::Standard | Variant::StandardFairy => Self {
+                claim: REPETITION_LIMIT,
+                automatic: AUTOMATIC_REPETITION_LIMIT,
+            },
+            _ => Self {
+                claim: REPETITION_LIMIT,
+                automatic: REPETITION_LIMIT,
+            },
+        }
+    }
+}
+
+/// Repetitions of current position in fight.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepetitionJS {
+    pub count: usize,
+    pub claimable: bool,
+    pub automatic: bool,
+}
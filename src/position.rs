@@ -1,9 +1,18 @@
-use js_sys::Map;
+use js_sys::{Array, Map};
 pub use shuuro::shuuro12::{attacks12::Attacks12, bitboard12::BB12, square12::Square12};
 use shuuro::Variant;
+use std::collections::HashMap;
 use wasm_bindgen::prelude::*;
 
+use crate::local_position::PieceJS;
+use crate::outcome::RepetitionRule;
 use crate::position_container::PositionContainer;
+use crate::search::parse_options;
+use crate::types::{
+    DestinationsMap, DivideMap, EvaluationValue, MoveResultValue, MovesMap, OutcomeValue,
+    PiecesMap, PlaceMovesMap, PlinthsMap, RepetitionRuleValue, RepetitionValue, SearchOptionsValue,
+    SearchResultValue, SquareList, StringList, ThreatsValue,
+};
 
 #[wasm_bindgen]
 pub struct ShuuroPosition {
@@ -14,6 +23,7 @@ pub struct ShuuroPosition {
 impl ShuuroPosition {
     #[wasm_bindgen(constructor)]
     pub fn new(variant: &str) -> Self {
+        crate::set_panic_hook();
         Self {
             shuuro: PositionContainer::new(Variant::from(&variant.to_string())),
         }
@@ -32,10 +42,10 @@ impl ShuuroPosition {
         self.shuuro.set_hand(s);
     }
 
-    /// Set sfen.
+    /// Set sfen. Throws `SfenErrorJS` and keeps previous position if sfen is invalid.
     #[wasm_bindgen]
-    pub fn set_sfen(&mut self, s: &str) {
-        self.shuuro.set_sfen(s);
+    pub fn set_sfen(&mut self, s: &str) -> Result<(), JsValue> {
+        self.shuuro.set_sfen(s).map_err(|e| e.to_js())
     }
 
     /// Get sfen for current position.
@@ -52,14 +62,14 @@ impl ShuuroPosition {
 
     /// All plinths on board.
     #[wasm_bindgen]
-    pub fn map_plinths(&self) -> Map {
-        self.shuuro.map_plinths()
+    pub fn map_plinths(&self) -> PlinthsMap {
+        plinths_map(self.shuuro.map_plinths()).unchecked_into()
     }
 
     /// All pieces on board.
     #[wasm_bindgen]
-    pub fn map_pieces(&self) -> Map {
-        self.shuuro.map_pieces()
+    pub fn map_pieces(&self) -> PiecesMap {
+        pieces_map(self.shuuro.map_pieces()).unchecked_into()
     }
 
     /// Starting credit for current variant.
@@ -79,17 +89,78 @@ impl ShuuroPosition {
         self.shuuro.last_move().to_string()
     }
 
+    /// Sfen after every move since position was set.
+    #[wasm_bindgen]
+    pub fn sfen_history(&self) -> StringList {
+        let ar = Array::new();
+        for sfen in self.shuuro.sfen_history() {
+            ar.push(&JsValue::from_str(&sfen));
+        }
+        ar.unchecked_into()
+    }
+
     /// Returns if side_to_move is in check.
     #[wasm_bindgen]
     pub fn is_check(&self) -> bool {
         self.shuuro.is_check()
     }
 
+    /// Current outcome: checkmate, stalemate, draws and winner.
+    #[wasm_bindgen]
+    pub fn outcome(&self) -> OutcomeValue {
+        self.shuuro.outcome().to_js().unchecked_into()
+    }
+
+    /// How many times current position appeared in fight,
+    /// and if draw can be claimed or is automatic.
+    #[wasm_bindgen]
+    pub fn repetition(&self) -> RepetitionValue {
+        serde_wasm_bindgen::to_value(&self.shuuro.repetition())
+            .unwrap_or(JsValue::NULL)
+            .unchecked_into()
+    }
+
+    #[wasm_bindgen]
+    pub fn repetition_rule(&self) -> RepetitionRuleValue {
+        serde_wasm_bindgen::to_value(&self.shuuro.repetition_rule())
+            .unwrap_or(JsValue::NULL)
+            .unchecked_into()
+    }
+
+    /// Set repetitions needed for claim and for automatic draw. Zero turns part of rule off.
+    #[wasm_bindgen]
+    pub fn set_repetition_rule(&mut self, claim: usize, automatic: usize) {
+        self.shuuro
+            .set_repetition_rule(RepetitionRule { claim, automatic });
+    }
+
+    /// Take back last move or placement. Returns false if there is nothing to undo.
+    #[wasm_bindgen]
+    pub fn undo(&mut self) -> bool {
+        self.shuuro.undo()
+    }
+
+    /// Play again last undone move.
+    #[wasm_bindgen]
+    pub fn redo(&mut self) -> bool {
+        self.shuuro.redo()
+    }
+
+    #[wasm_bindgen]
+    pub fn can_undo(&self) -> bool {
+        self.shuuro.can_undo()
+    }
+
+    #[wasm_bindgen]
+    pub fn can_redo(&self) -> bool {
+        self.shuuro.can_redo()
+    }
+
     // Deploy part
 
     /// Squares where piece can be placed.
-    pub fn place_moves(&mut self, piece: char) -> Map {
-        self.shuuro.place_moves(piece)
+    pub fn place_moves(&mut self, piece: char) -> PlaceMovesMap {
+        moves_map(self.shuuro.place_moves(piece)).unchecked_into()
     }
 
     /// Count how many pieces are left in hand.
@@ -108,20 +179,198 @@ impl ShuuroPosition {
 
     /// All legal moves for square.
     #[wasm_bindgen]
-    pub fn legal_moves(&self, color: u8) -> Map {
-        self.shuuro.legal_moves(color)
+    pub fn legal_moves(&self, color: u8) -> MovesMap {
+        moves_map(self.shuuro.legal_moves(color)).unchecked_into()
+    }
+
+    /// Destinations for every piece, with pieces that pawn can promote to.
+    #[wasm_bindgen]
+    pub fn legal_destinations(&self, color: u8) -> DestinationsMap {
+        serde_wasm_bindgen::to_value(&self.shuuro.legal_destinations(color))
+            .unwrap_or(JsValue::NULL)
+            .unchecked_into()
+    }
+
+    /// Promotion pieces for move like `e7_e8`. Empty if move does not promote.
+    #[wasm_bindgen]
+    pub fn promotions(&self, game_move: &str) -> StringList {
+        let ar = Array::new();
+        for piece in self.shuuro.promotions(game_move) {
+            ar.push(&JsValue::from_str(&piece));
+        }
+        ar.unchecked_into()
+    }
+
+    /// Legal destinations of piece on square. Empty if square has no piece of side to move.
+    #[wasm_bindgen]
+    pub fn legal_moves_from(&self, square: &str) -> SquareList {
+        square_list(self.shuuro.legal_moves_from(square))
+    }
+
+    /// Check if fight move or placement is legal for side to move.
+    #[wasm_bindgen]
+    pub fn is_legal(&self, game_move: &str) -> bool {
+        self.shuuro.is_legal(game_move)
+    }
+
+    /// Premove destinations for side that waits. Checks are ignored.
+    #[wasm_bindgen]
+    pub fn premove_moves(&self) -> MovesMap {
+        moves_map(self.shuuro.premove_moves()).unchecked_into()
+    }
+
+    /// Queue premove in sfen format. Returns false if piece can not go there.
+    #[wasm_bindgen]
+    pub fn premove(&mut self, game_move: &str) -> bool {
+        self.shuuro.premove(game_move)
+    }
+
+    #[wasm_bindgen]
+    pub fn premoves(&self) -> StringList {
+        let ar = Array::new();
+        for m in self.shuuro.premove_queue() {
+            ar.push(&JsValue::from_str(&m));
+        }
+        ar.unchecked_into()
+    }
+
+    #[wasm_bindgen]
+    pub fn cancel_premoves(&mut self) {
+        self.shuuro.cancel_premoves();
+    }
+
+    /// Squares attacked by color, including squares with own pieces.
+    /// Plinths block sliding pieces.
+    #[wasm_bindgen]
+    pub fn attacked_squares(&self, color: u8) -> SquareList {
+        square_list(self.shuuro.attacked_squares(color))
+    }
+
+    /// Squares of white and black pieces that attack square.
+    #[wasm_bindgen]
+    pub fn attackers_of(&self, square: &str) -> SquareList {
+        square_list(self.shuuro.attackers_of(square))
+    }
+
+    /// Checkers, check rays, squares that block check and pinned pieces for side to move.
+    #[wasm_bindgen]
+    pub fn threats(&self) -> ThreatsValue {
+        serde_wasm_bindgen::to_value(&self.shuuro.threats())
+            .unwrap_or(JsValue::NULL)
+            .unchecked_into()
+    }
+
+    /// Get move from server and play. Returns `{ game_move, outcome, premove }` or null if move is illegal.
+    /// After move, first queued premove is played or whole queue is cancelled.
+    /// Promotion piece can be chosen with `=`, like `e7_e8=N`.
+    #[wasm_bindgen]
+    pub fn make_move(&mut self, game_move: String) -> MoveResultValue {
+        match self.shuuro.make_move(game_move) {
+            Some(result) => result.to_js().unchecked_into(),
+            None => JsValue::NULL.unchecked_into(),
+        }
     }
 
-    /// Get move from server and play
+    /// Zobrist hash of pieces, plinths, hands and side to move, as `BigInt`.
     #[wasm_bindgen]
-    pub fn make_move(&mut self, game_move: String) -> Option<String> {
-        self.shuuro.make_move(game_move)
+    pub fn zobrist(&self) -> u64 {
+        self.shuuro.zobrist()
+    }
+
+    /// Zobrist hash as 16 hex digits.
+    #[wasm_bindgen]
+    pub fn zobrist_hex(&self) -> String {
+        format!("{:016x}", self.shuuro.zobrist())
     }
 
     #[wasm_bindgen]
     pub fn variant(&self) -> String {
         self.shuuro.variant()
     }
+
+    // Perft part
+
+    /// Count leaf nodes from current position.
+    #[wasm_bindgen]
+    pub fn perft(&self, depth: u8) -> u64 {
+        self.shuuro.perft(depth)
+    }
+
+    /// Leaf nodes for every legal move from current position.
+    #[wasm_bindgen]
+    pub fn divide(&self, depth: u8) -> DivideMap {
+        let map = Map::new();
+        for (game_move, nodes) in self.shuuro.divide(depth) {
+            map.set(&JsValue::from_str(&game_move), &JsValue::from(nodes));
+        }
+        map.unchecked_into()
+    }
+
+    // Computer part
+
+    /// Best move for side to move. Options are `{ depth, nodes, time }`, time is in ms.
+    /// Throws `SearchErrorJS` when options have wrong types.
+    #[wasm_bindgen]
+    pub fn best_move(&self, options: SearchOptionsValue) -> Result<SearchResultValue, JsValue> {
+        let options = parse_options(options.into()).map_err(|e| e.to_js())?;
+        let result = self.shuuro.best_move(options);
+        Ok(serde_wasm_bindgen::to_value(&result)
+            .unwrap_or(JsValue::NULL)
+            .unchecked_into())
+    }
+
+    /// Evaluation from white side in centipawns, with breakdown.
+    #[wasm_bindgen]
+    pub fn evaluate(&self) -> EvaluationValue {
+        let eval = self.shuuro.evaluate();
+        serde_wasm_bindgen::to_value(&eval)
+            .unwrap_or(JsValue::NULL)
+            .unchecked_into()
+    }
+
+    /// Placement for side to move in `P@sq` format. Level is from 1 (weakest) to 3.
+    /// Seed changes noise on weakest level, same seed gives same placement.
+    #[wasm_bindgen]
+    pub fn deploy_move(&self, level: u8, seed: u32) -> Option<String> {
+        self.shuuro.deploy_move(level, seed as u64)
+    }
+
+    // Notation part
+
+    /// Move in SAN-like notation, like `Nxe4+`, `N@e4` or `+N`.
+    /// Figurines are used for standard pieces when `figurine` is true.
+    #[wasm_bindgen]
+    pub fn to_san(&self, game_move: &str, figurine: bool) -> Option<String> {
+        self.shuuro.to_san(game_move, figurine)
+    }
+
+    /// Move in sfen format from text like `Nf3`, `exd5`, `Q@c10` or `g1f3`.
+    /// Throws `MoveParseErrorJS` when move is invalid, illegal or ambiguous.
+    #[wasm_bindgen]
+    pub fn parse_move(&self, input: &str) -> Result<String, JsValue> {
+        self.shuuro.parse_move(input).map_err(|e| e.to_js())
+    }
+
+    /// Legal moves that start with partially typed text.
+    #[wasm_bindgen]
+    pub fn complete_move(&self, prefix: &str) -> StringList {
+        let ar = Array::new();
+        for m in self.shuuro.complete_move(prefix) {
+            ar.push(&JsValue::from_str(&m));
+        }
+        ar.unchecked_into()
+    }
+}
+
+impl ShuuroPosition {
+    /// Native position used by this class.
+    pub fn container(&self) -> &PositionContainer {
+        &self.shuuro
+    }
+
+    pub fn container_mut(&mut self) -> &mut PositionContainer {
+        &mut self.shuuro
+    }
 }
 
 impl Default for ShuuroPosition {
@@ -129,3 +378,45 @@ impl Default for ShuuroPosition {
         Self::new("shuuro")
     }
 }
+
+pub(crate) fn pieces_map(pieces: HashMap<String, PieceJS>) -> Map {
+    let map = Map::new();
+    for (sq, piece) in pieces {
+        if let Ok(piece) = serde_wasm_bindgen::to_value(&piece) {
+            map.set(&JsValue::from_str(&sq), &piece);
+        }
+    }
+    map
+}
+
+/// Plinths as pieces with `l-piece` role.
+pub(crate) fn plinths_map(squares: Vec<String>) -> Map {
+    let plinths = squares.into_iter().map(|sq| {
+        let plinth = PieceJS {
+            role: String::from("l-piece"),
+            color: String::from("white"),
+        };
+        (sq, plinth)
+    });
+    pieces_map(plinths.collect())
+}
+
+fn square_list(squares: Vec<String>) -> SquareList {
+    let ar = Array::new();
+    for sq in squares {
+        ar.push(&JsValue::from_str(&sq));
+    }
+    ar.unchecked_into()
+}
+
+fn moves_map(moves: HashMap<String, Vec<String>>) -> Map {
+    let map = Map::new();
+    for (key, squares) in moves {
+        let value = Array::new();
+        for sq in squares {
+            value.push(&JsValue::from_str(&sq));
+        }
+        map.set(&JsValue::from_str(&key), &JsValue::from(value));
+    }
+    map
+}
@@ -1,21 +1,30 @@
-use crate::local_position::LocalPosition;
-use js_sys::Map;
+use crate::control::Threats;
+use crate::eval::Evaluation;
+use crate::local_position::{LocalPosition, PieceJS};
+use crate::notation::MoveParseErrorJS;
+use crate::outcome::{MoveResultJS, OutcomeJS, RepetitionJS, RepetitionRule};
+use crate::promotion::DestinationJS;
+use crate::search::{SearchOptions, SearchResult};
+use crate::sfen::{board_size, validate_sfen, SfenErrorJS};
 use shuuro::shuuro12::{
     attacks12::Attacks12, bitboard12::BB12, position12::P12, square12::Square12,
 };
 use shuuro::shuuro6::{attacks6::Attacks6, bitboard6::BB6, position6::P6, square6::Square6};
 use shuuro::shuuro8::{attacks8::Attacks8, bitboard8::BB8, position8::P8, square8::Square8};
 use shuuro::{Color, Variant};
-use wasm_bindgen::prelude::wasm_bindgen;
-
-#[wasm_bindgen]
-extern "C" {
-    // Use `js_namespace` here to bind `console.log(..)` instead of just
-    // `log(..)`
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-}
+use std::collections::HashMap;
+
 macro_rules! local_position {
+    // mutate without params
+    ($self: ident, $method: ident, mut) => {
+        match $self.variant {
+            Variant::Standard | Variant::StandardFairy => $self.local8.as_mut().unwrap().$method(),
+            Variant::ShuuroMini | Variant::ShuuroMiniFairy => {
+                $self.local6.as_mut().unwrap().$method()
+            }
+            _ => $self.local12.as_mut().unwrap().$method(),
+        }
+    };
     // mutate
     ($self: ident, $method: ident, $param: expr, $mut: expr, $ret: expr) => {
         match $self.variant {
@@ -41,6 +50,19 @@ macro_rules! local_position {
             _ => $self.local12.as_mut().unwrap().$part($param),
         }
     };
+    // reading with many params
+    ($self: ident, $method: ident, ($($param: expr),+)) => {
+        match $self.variant {
+            Variant::Standard | Variant::StandardFairy => {
+                $self.local8.as_ref().unwrap().$method($($param),+)
+            }
+
+            Variant::ShuuroMini | Variant::ShuuroMiniFairy => {
+                $self.local6.as_ref().unwrap().$method($($param),+)
+            }
+            _ => $self.local12.as_ref().unwrap().$method($($param),+),
+        }
+    };
     // reading
     ($self: ident, $method: ident) => {
         match $self.variant {
@@ -88,6 +110,9 @@ type Local6 = LocalPosition<
     P6<Square6, BB6<Square6>>,
 >;
 
+/// Position for every variant, without any JS types.
+/// `ShuuroPosition` is thin wasm adapter over it.
+#[derive(Clone)]
 pub struct PositionContainer {
     local8: Option<Local8>,
     local12: Option<Local12>,
@@ -136,8 +161,9 @@ impl PositionContainer {
     }
 
     #[inline]
-    pub fn set_sfen(&mut self, s: &str) {
-        local_position!(self, set_sfen, s, true, true);
+    pub fn set_sfen(&mut self, s: &str) -> Result<(), SfenErrorJS> {
+        validate_sfen(s, board_size(self.variant))?;
+        local_position!(self, set_sfen, s, true, true)
     }
 
     #[inline]
@@ -156,12 +182,12 @@ impl PositionContainer {
     }
 
     #[inline]
-    pub fn map_plinths(&self) -> Map {
+    pub fn map_plinths(&self) -> Vec<String> {
         local_position!(self, map_plinths)
     }
 
     #[inline]
-    pub fn map_pieces(&self) -> Map {
+    pub fn map_pieces(&self) -> HashMap<String, PieceJS> {
         local_position!(self, map_pieces)
     }
 
@@ -175,13 +201,63 @@ impl PositionContainer {
         local_position!(self, last_move)
     }
 
+    #[inline]
+    pub fn sfen_history(&self) -> Vec<String> {
+        local_position!(self, sfen_history)
+    }
+
     #[inline]
     pub fn is_check(&self) -> bool {
         local_position!(self, is_check)
     }
 
     #[inline]
-    pub fn place_moves(&mut self, piece: char) -> Map {
+    pub fn outcome(&self) -> OutcomeJS {
+        local_position!(self, outcome)
+    }
+
+    #[inline]
+    pub fn repetition(&self) -> RepetitionJS {
+        local_position!(self, repetition)
+    }
+
+    #[inline]
+    pub fn repetition_rule(&self) -> RepetitionRule {
+        local_position!(self, repetition_rule)
+    }
+
+    #[inline]
+    pub fn set_repetition_rule(&mut self, rule: RepetitionRule) {
+        local_position!(self, set_repetition_rule, rule, true, true)
+    }
+
+    #[inline]
+    pub fn undo(&mut self) -> bool {
+        local_position!(self, undo, mut)
+    }
+
+    #[inline]
+    pub fn redo(&mut self) -> bool {
+        local_position!(self, redo, mut)
+    }
+
+    #[inline]
+    pub fn clear_undo(&mut self) {
+        local_position!(self, clear_undo, mut)
+    }
+
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        local_position!(self, can_undo)
+    }
+
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        local_position!(self, can_redo)
+    }
+
+    #[inline]
+    pub fn place_moves(&mut self, piece: char) -> HashMap<String, Vec<String>> {
         local_position!(self, place_moves, piece, false, false)
     }
 
@@ -195,13 +271,131 @@ impl PositionContainer {
     }
 
     #[inline]
-    pub fn legal_moves(&self, color: u8) -> Map {
+    pub fn legal_moves(&self, color: u8) -> HashMap<String, Vec<String>> {
         let color = Color::from(color as usize);
         local_position!(self, legal_moves, color)
     }
 
     #[inline]
-    pub fn make_move(&mut self, game_move: String) -> Option<String> {
+    pub fn legal_destinations(&self, color: u8) -> HashMap<String, Vec<DestinationJS>> {
+        let color = Color::from(color as usize);
+        local_position!(self, legal_destinations, color)
+    }
+
+    /// Pieces that move can promote to, empty if it is not promotion.
+    #[inline]
+    pub fn promotions(&self, game_move: &str) -> Vec<String> {
+        local_position!(self, promotions, game_move)
+    }
+
+    #[inline]
+    pub fn legal_moves_from(&self, square: &str) -> Vec<String> {
+        local_position!(self, legal_moves_from, square)
+    }
+
+    #[inline]
+    pub fn is_legal(&self, game_move: &str) -> bool {
+        local_position!(self, is_legal, game_move)
+    }
+
+    #[inline]
+    pub fn premove_moves(&self) -> HashMap<String, Vec<String>> {
+        local_position!(self, premove_moves)
+    }
+
+    #[inline]
+    pub fn premove(&mut self, game_move: &str) -> bool {
+        local_position!(self, premove, game_move, false, false)
+    }
+
+    #[inline]
+    pub fn premove_queue(&self) -> Vec<String> {
+        local_position!(self, premove_queue)
+    }
+
+    #[inline]
+    pub fn cancel_premoves(&mut self) {
+        local_position!(self, cancel_premoves, mut)
+    }
+
+    #[inline]
+    pub fn make_move(&mut self, game_move: String) -> Option<MoveResultJS> {
         local_position!(self, make_move, game_move, false, false)
     }
+
+    /// Play move, queued premoves of other side are not played.
+    #[inline]
+    pub(crate) fn play_move(&mut self, game_move: &str) -> Option<MoveResultJS> {
+        local_position!(self, play_move, game_move, false, false)
+    }
+}
+
+impl PositionContainer {
+    #[inline]
+    pub fn zobrist(&self) -> u64 {
+        local_position!(self, zobrist)
+    }
+
+    #[inline]
+    pub fn fight_moves(&self) -> Vec<String> {
+        local_position!(self, fight_moves)
+    }
+
+    #[inline]
+    pub fn perft(&self, depth: u8) -> u64 {
+        local_position!(self, perft, depth)
+    }
+
+    #[inline]
+    pub fn divide(&self, depth: u8) -> Vec<(String, u64)> {
+        local_position!(self, divide, depth)
+    }
+
+    #[inline]
+    pub fn best_move(&self, options: SearchOptions) -> SearchResult {
+        local_position!(self, best_move, options)
+    }
+
+    #[inline]
+    pub fn deploy_move(&self, level: u8, seed: u64) -> Option<String> {
+        local_position!(self, deploy_move, (level, seed))
+    }
+
+    #[inline]
+    pub fn evaluate(&self) -> Evaluation {
+        local_position!(self, evaluate)
+    }
+
+    /// Move in SAN-like notation, `None` if it is not legal.
+    #[inline]
+    pub fn to_san(&self, game_move: &str, figurine: bool) -> Option<String> {
+        local_position!(self, to_san, (game_move, figurine))
+    }
+
+    #[inline]
+    pub fn attacked_squares(&self, color: u8) -> Vec<String> {
+        let color = Color::from(color as usize);
+        local_position!(self, attacked_squares, color)
+    }
+
+    #[inline]
+    pub fn attackers_of(&self, square: &str) -> Vec<String> {
+        local_position!(self, attackers_of, square)
+    }
+
+    #[inline]
+    pub fn threats(&self) -> Threats {
+        local_position!(self, threats)
+    }
+
+    /// Move in sfen format from SAN, placement or coordinates.
+    #[inline]
+    pub fn parse_move(&self, input: &str) -> Result<String, MoveParseErrorJS> {
+        local_position!(self, parse_move, input)
+    }
+
+    #[inline]
+    pub fn complete_move(&self, prefix: &str) -> Vec<String> {
+        local_position!(self, complete_move, prefix)
+    }
 }
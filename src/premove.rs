@@ -0,0 +1,130 @@
+use shuuro::{
+    attacks::Attacks, bitboard::BitBoard, position::Position, Color, Move, Piece, PieceType, Square,
+};
+use std::{collections::HashMap, hash::Hash};
+
+use crate::board::{coords, Occupancy};
+use crate::local_position::{fight_move, opponent, LocalPosition};
+use crate::outcome::PremoveJS;
+use crate::promotion::split_promotion;
+
+impl<S, B, A, P> LocalPosition<S, B, A, P>
+where
+    S: Square + Hash,
+    B: BitBoard<S>,
+    A: Attacks<S, B>,
+    P: Position<S, B, A> + Clone,
+{
+    /// Destinations for side that waits, on board after queued premoves.
+    /// Checks are ignored and enemy pieces can be captured.
+    pub fn premove_moves(&self) -> HashMap<String, Vec<String>> {
+        let occupancy = self.premove_occupancy();
+        let color = opponent(self.state.side_to_move());
+        let mut map = HashMap::new();
+        for (sq, piece) in occupancy.pieces.iter().filter(|(_, p)| p.color == color) {
+            let targets = Self::premove_targets(&occupancy, *sq, piece);
+            if !targets.is_empty() {
+                map.insert(sq.to_string(), targets);
+            }
+        }
+        map
+    }
+
+    /// Add premove at the end of queue. Returns false if piece can not go there.
+    /// Promotion piece can be chosen with `=`.
+    pub fn premove(&mut self, game_move: &str) -> bool {
+        let (fight, promotion) = split_promotion(game_move);
+        let (from, to) = match fight.split_once('_') {
+            Some(squares) => squares,
+            None => return false,
+        };
+        let valid = self
+            .premove_moves()
+            .get(from)
+            .map(|targets| targets.iter().any(|sq| sq == to))
+            .unwrap_or(false);
+        if valid {
+            let mut game_move = fight_move(from, to);
+            if let Some(piece) = promotion {
+                game_move.push('=');
+                game_move.push(piece.to_ascii_uppercase());
+            }
+            self.premoves.push(game_move);
+        }
+        valid
+    }
+
+    pub fn premove_queue(&self) -> Vec<String> {
+        self.premoves.clone()
+    }
+
+    pub fn cancel_premoves(&mut self) {
+        self.premoves.clear();
+    }
+
+    /// Play first premove, now that its side is to move.
+    /// Premove that is not legal cancels whole queue.
+    pub(crate) fn play_premove(&mut self) -> Option<PremoveJS> {
+        if self.premoves.is_empty() {
+            return None;
+        }
+        let game_move = self.premoves.remove(0);
+        if !self.outcome().game_over {
+            if let Some(result) = self.play_move(&game_move) {
+                return Some(PremoveJS {
+                    game_move: result.game_move,
+                    played: true,
+                    outcome: Some(result.outcome),
+                });
+            }
+        }
+        self.premoves.clear();
+        Some(PremoveJS {
+            game_move,
+            played: false,
+            outcome: None,
+        })
+    }
+
+    /// Pieces and plinths with queued premoves already played.
+    fn premove_occupancy(&self) -> Occupancy<S> {
+        let mut occupancy = Self::occupancy(&self.state);
+        for m in self.premoves.iter() {
+            let (m, _) = split_promotion(m);
+            if let Some(Move::Normal { from, to, .. }) = Move::<S>::from_sfen(m) {
+                if let Some(piece) = occupancy.pieces.remove(&from) {
+                    occupancy.pieces.insert(to, piece);
+                }
+            }
+        }
+        occupancy
+    }
+
+    /// Squares that piece attacks, without own pieces. Pawn can also go forward,
+    /// to square that king would reach on same file.
+    fn premove_targets(occupancy: &Occupancy<S>, sq: S, piece: &Piece) -> Vec<String> {
+        let mut targets = Self::piece_attacks(occupancy, sq, piece);
+        if piece.piece_type == PieceType::Pawn {
+            let (file, rank) = coords(&sq);
+            let forward = match piece.color {
+                Color::White => Some(rank + 1),
+                _ => rank.checked_sub(1),
+            };
+            let step = A::get_non_sliding_attacks(PieceType::King, &sq, piece.color, B::empty())
+                .into_iter()
+                .find(|to| Some(coords(to)) == forward.map(|rank| (file, rank)));
+            if let Some(to) = step.filter(|to| !occupancy.plinths.contains(to)) {
+                targets.push(to);
+            }
+        }
+        targets.retain(|to| {
+            occupancy
+                .pieces
+                .get(to)
+                .map(|p| p.color != piece.color)
+                .unwrap_or(true)
+        });
+        targets.sort_by_key(coords);
+        targets.iter().map(|to| to.to_string()).collect()
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use shuuro::{
+    attacks::Attacks, bitboard::BitBoard, position::Position, Color, Piece, PieceType, Square,
+    Variant,
+};
+use std::{collections::HashMap, hash::Hash};
+
+use crate::local_position::LocalPosition;
+use crate::sfen::{board_size, replace_piece, square_coords};
+
+/// Destination of piece. Promotion has piece letters that pawn can choose there,
+/// and it is empty for other moves.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DestinationJS {
+    pub square: String,
+    pub promotion: Vec<String>,
+}
+
+/// Pieces that pawn can become. Fairy variants add their pieces.
+pub fn promotion_types(variant: Variant) -> Vec<PieceType> {
+    let mut types = vec![
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+    ];
+    if matches!(
+        variant,
+        Variant::ShuuroFairy | Variant::StandardFairy | Variant::ShuuroMiniFairy
+    ) {
+        types.extend([
+            PieceType::Chancellor,
+            PieceType::ArchBishop,
+            PieceType::Giraffe,
+        ]);
+    }
+    types
+}
+
+/// Fight move and promotion letter, from `e7_e8=N`.
+pub fn split_promotion(game_move: &str) -> (&str, Option<char>) {
+    match game_move.split_once('=') {
+        Some((game_move, piece)) => (game_move, piece.chars().next()),
+        None => (game_move, None),
+    }
+}
+
+impl<S, B, A, P> LocalPosition<S, B, A, P>
+where
+    S: Square + Hash,
+    B: BitBoard<S>,
+    A: Attacks<S, B>,
+    P: Position<S, B, A> + Clone,
+{
+    /// Destinations for every piece of side to move, with promotion choices.
+    pub fn legal_destinations(&self, color: Color) -> HashMap<String, Vec<DestinationJS>> {
+        let mut map = HashMap::new();
+        for (from, targets) in self.legal_moves(color) {
+            let destinations = targets
+                .into_iter()
+                .map(|square| DestinationJS {
+                    promotion: self.promotion_letters(&from, &square),
+                    square,
+                })
+                .collect();
+            map.insert(from, destinations);
+        }
+        map
+    }
+
+    /// Piece letters that move can promote to. Empty if move is not promotion.
+    pub fn promotions(&self, game_move: &str) -> Vec<String> {
+        match split_promotion(game_move).0.split_once('_') {
+            Some((from, to)) if self.is_legal(&format!("{}_{}", from, to)) => {
+                self.promotion_letters(from, to)
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Check that chosen piece is allowed for promotion.
+    pub(crate) fn valid_promotion(&self, game_move: &str, piece: char) -> bool {
+        self.promotions(game_move)
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(&piece.to_string()))
+    }
+
+    /// Put chosen piece on square where pawn promoted.
+    /// Board is set again from sfen, because position has no other way to change piece.
+    /// That also starts sfen history of position again, so moves are kept in `history`.
+    pub(crate) fn set_promoted(state: &mut P, square: &str, piece: char, color: Color) -> bool {
+        let piece_type = match Piece::from_sfen(piece) {
+            Some(p) => p.piece_type,
+            None => return false,
+        };
+        let piece = Piece { piece_type, color }.to_string();
+        let sfen = state.generate_sfen();
+        let sfen = square_coords(square)
+            .zip(piece.chars().next())
+            .and_then(|(square, piece)| replace_piece(&sfen, square, piece));
+        match sfen {
+            Some(sfen) => state.set_sfen(&sfen).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Promotion letters for pawn of side to move that reaches last rank.
+    fn promotion_letters(&self, from: &str, to: &str) -> Vec<String> {
+        let stm = self.state.side_to_move();
+        let pawn = self.state.player_bb(stm).into_iter().any(|sq| {
+            sq.to_string() == from
+                && self
+                    .state
+                    .piece_at(sq)
+                    .map(|p| p.piece_type == PieceType::Pawn)
+                    .unwrap_or(false)
+        });
+        let size = board_size(self.state.variant());
+        let last = match stm {
+            Color::White => size,
+            _ => 1,
+        };
+        if !pawn || square_coords(to).map(|sq| sq.1) != Some(last) {
+            return vec![];
+        }
+        promotion_types(self.state.variant())
+            .into_iter()
+            .map(|piece_type| {
+                Piece {
+                    piece_type,
+                    color: stm,
+                }
+                .to_string()
+            })
+            .collect()
+    }
+}
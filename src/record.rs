@@ -0,0 +1,486 @@
+use js_sys::Array;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::game::{Phase, ShuuroGame};
+use crate::types::StringList;
+
+/// Section headers in order of game phases.
+pub(crate) const SHOP: &str = "Shop:";
+pub(crate) const DEPLOY: &str = "Deploy:";
+pub(crate) const FIGHT: &str = "Fight:";
+
+/// Results that can end move text.
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Longest line of move text written by `write`.
+const LINE_LENGTH: usize = 80;
+
+/// Reason why game record was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordErrorKind {
+    Tag,
+    Section,
+    Comment,
+    IllegalMove,
+    Result,
+}
+
+/// This represents record error thrown to JS.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordErrorJS {
+    pub kind: RecordErrorKind,
+    pub message: String,
+}
+
+impl RecordErrorJS {
+    pub fn new(kind: RecordErrorKind, message: &str) -> Self {
+        Self {
+            kind,
+            message: String::from(message),
+        }
+    }
+
+    pub fn to_js(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self).unwrap_or_else(|_| JsValue::from_str(&self.message))
+    }
+}
+
+/// Move in sfen format with comment written after it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordMove {
+    pub game_move: String,
+    pub comment: Option<String>,
+}
+
+/// Record of whole game in PGN-like format:
+///
+/// ```text
+/// [Variant "standard"]
+/// [White "Anna"]
+/// [Result "1-0"]
+/// [Plinths "8/8/8/2L05/8/8/8/8 w - 1"]
+///
+/// Shop:
+/// +Q +q +P {cheap pawn} +r
+///
+/// Deploy:
+/// 1. K@e1 K@e8 2. Q@d1 ...
+///
+/// Fight:
+/// 1. e4 e5 2. Qh5 ... 1-0
+/// ```
+///
+/// `Plinths` tag is starting sfen with plinths. Game with `Sfen` tag starts
+/// from that deploy or fight position, without shop.
+/// Variations in parentheses are skipped by reader.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShuuroRecord {
+    tags: Vec<(String, String)>,
+    comment: Option<String>,
+    moves: Vec<RecordMove>,
+}
+
+#[wasm_bindgen]
+impl ShuuroRecord {
+    #[wasm_bindgen(constructor)]
+    pub fn new(variant: &str) -> Self {
+        let mut record = Self::default();
+        record.set_tag("Variant", variant);
+        record.set_tag("Result", "*");
+        record
+    }
+
+    /// Set tag like "White", "Black", "Result", "TimeControl" or "Plinths".
+    #[wasm_bindgen]
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|t| t.0 == name) {
+            Some(tag) => tag.1 = String::from(value),
+            None => self.tags.push((String::from(name), String::from(value))),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn tag(&self, name: &str) -> Option<String> {
+        self.tags.iter().find(|t| t.0 == name).map(|t| t.1.clone())
+    }
+
+    /// Add purchase, placement or fight move in sfen format.
+    #[wasm_bindgen]
+    pub fn push(&mut self, game_move: String, comment: Option<String>) {
+        self.moves.push(RecordMove { game_move, comment });
+    }
+
+    /// All moves in sfen format.
+    #[wasm_bindgen]
+    pub fn moves(&self) -> StringList {
+        let ar = Array::new();
+        for m in self.moves.iter() {
+            ar.push(&JsValue::from_str(&m.game_move));
+        }
+        ar.unchecked_into()
+    }
+
+    /// Record as text. Throws `RecordErrorJS` if some move is illegal.
+    #[wasm_bindgen]
+    pub fn write(&self) -> Result<String, JsValue> {
+        self.write_text().map_err(|e| e.to_js())
+    }
+
+    /// Read record from text and replay every move.
+    /// Throws `RecordErrorJS` if text is invalid or some move is illegal.
+    #[wasm_bindgen]
+    pub fn read(text: &str) -> Result<ShuuroRecord, JsValue> {
+        Self::read_text(text).map_err(|e| e.to_js())
+    }
+}
+
+impl ShuuroRecord {
+    pub fn variant(&self) -> String {
+        self.tag("Variant")
+            .unwrap_or_else(|| String::from("shuuro"))
+    }
+
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// Comment before first move.
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    pub fn set_comment(&mut self, comment: Option<String>) {
+        self.comment = comment;
+    }
+
+    pub fn record_moves(&self) -> &[RecordMove] {
+        &self.moves
+    }
+
+    /// Game after every move is played.
+    pub fn replay(&self) -> Result<ShuuroGame, RecordErrorJS> {
+        self.clone().replay_moves().map(|r| r.0)
+    }
+
+    pub fn write_text(&self) -> Result<String, RecordErrorJS> {
+        let (game, written) = self.clone().replay_moves()?;
+        self.check_result(&game)?;
+        let mut text = String::new();
+        for (name, value) in self.tags.iter() {
+            text.push_str(&tag_line(name, value));
+        }
+        if let Some(comment) = &self.comment {
+            text.push_str(&format!("\n{{{}}}\n", comment));
+        }
+        for header in [SHOP, DEPLOY, FIGHT] {
+            let mut tokens = vec![];
+            let mut number = 1;
+            let mut white_moved = false;
+            for (m, (san, side)) in self.moves.iter().zip(written.iter()) {
+                if section(&m.game_move) != header {
+                    continue;
+                }
+                if header != SHOP {
+                    if side == "w" {
+                        if white_moved {
+                            number += 1;
+                        }
+                        tokens.push(format!("{}.", number));
+                    } else if !white_moved {
+                        tokens.push(format!("{}...", number));
+                    }
+                    white_moved = side == "w";
+                    if side == "b" {
+                        number += 1;
+                    }
+                }
+                tokens.push(san.clone());
+                if let Some(comment) = &m.comment {
+                    tokens.push(format!("{{{}}}", comment));
+                }
+            }
+            if !tokens.is_empty() {
+                text.push_str(&format!("\n{}\n", header));
+                text.push_str(&wrap(&tokens));
+                text.push('\n');
+            }
+        }
+        let result = self.tag("Result").unwrap_or_else(|| String::from("*"));
+        text.push_str(&format!("\n{}\n", result));
+        Ok(text)
+    }
+
+    /// Read record from text and replay every move. Moves are stored in sfen format.
+    pub fn read_text(text: &str) -> Result<Self, RecordErrorJS> {
+        let mut record = Self::default();
+        let mut lines = text.lines().peekable();
+        while let Some(line) = lines.peek() {
+            let line = line.trim();
+            if line.is_empty() {
+                lines.next();
+                continue;
+            }
+            if !line.starts_with('[') {
+                break;
+            }
+            let (name, value) = parse_tag(line)?;
+            record.set_tag(&name, &value);
+            lines.next();
+        }
+        let body: Vec<&str> = lines.collect();
+        let body = body.join("\n");
+
+        let mut current = None;
+        let mut result = None;
+        let mut depth = 0;
+        for token in tokenize(&body)? {
+            match token.as_str() {
+                "(" => depth += 1,
+                ")" if depth == 0 => {
+                    return Err(RecordErrorJS::new(
+                        RecordErrorKind::Section,
+                        "variation is closed, but not opened",
+                    ))
+                }
+                ")" => depth -= 1,
+                _ => (),
+            }
+            if depth > 0 || token == ")" {
+                continue;
+            }
+            if result.is_some() {
+                return Err(RecordErrorJS::new(
+                    RecordErrorKind::Result,
+                    "nothing can be written after result",
+                ));
+            }
+            if let Some(comment) = token.strip_prefix('{') {
+                let comment = String::from(comment.trim());
+                match record.moves.last_mut() {
+                    Some(m) => m.comment = Some(comment),
+                    None => record.comment = Some(comment),
+                }
+                continue;
+            }
+            if [SHOP, DEPLOY, FIGHT].contains(&token.as_str()) {
+                current = Some(token);
+                continue;
+            }
+            if RESULTS.contains(&token.as_str()) {
+                result = Some(token);
+                continue;
+            }
+            let game_move = strip_number(&token);
+            if game_move.is_empty() {
+                continue;
+            }
+            let header = match &current {
+                Some(header) => header.as_str(),
+                None => {
+                    return Err(RecordErrorJS::new(
+                        RecordErrorKind::Section,
+                        &format!("move '{}' is not in any section", game_move),
+                    ))
+                }
+            };
+            if section(game_move) != header {
+                return Err(RecordErrorJS::new(
+                    RecordErrorKind::Section,
+                    &format!("move '{}' can not be in section '{}'", game_move, header),
+                ));
+            }
+            record.push(String::from(game_move), None);
+        }
+        if depth > 0 {
+            return Err(RecordErrorJS::new(
+                RecordErrorKind::Section,
+                "variation is not closed",
+            ));
+        }
+        if let (Some(result), Some(tag)) = (&result, record.tag("Result")) {
+            if *result != tag {
+                return Err(RecordErrorJS::new(
+                    RecordErrorKind::Result,
+                    &format!("result '{}' is different from tag '{}'", result, tag),
+                ));
+            }
+        } else if let Some(result) = result {
+            record.set_tag("Result", &result);
+        }
+        let (game, _) = record.replay_moves()?;
+        record.check_result(&game)?;
+        Ok(record)
+    }
+
+    /// Play every move. Moves written in SAN are changed to sfen format.
+    /// Returns game and every move in SAN with side that played it.
+    fn replay_moves(&mut self) -> Result<(ShuuroGame, Vec<(String, String)>), RecordErrorJS> {
+        let mut game = ShuuroGame::new(&self.variant());
+        let start = match (self.tag("Sfen"), self.tag("Plinths")) {
+            (Some(sfen), _) => game.set_position(&sfen),
+            (None, Some(sfen)) => game.set_start(&sfen),
+            _ => Ok(()),
+        };
+        start.map_err(|e| RecordErrorJS::new(RecordErrorKind::Tag, &e.message))?;
+        let mut written = vec![];
+        for (ply, m) in self.moves.iter_mut().enumerate() {
+            let illegal = |message: &str| {
+                RecordErrorJS::new(
+                    RecordErrorKind::IllegalMove,
+                    &format!("move {} '{}': {}", ply + 1, m.game_move, message),
+                )
+            };
+            if section(&m.game_move) == SHOP {
+                game.apply_move(m.game_move.clone())
+                    .map_err(|e| illegal(&e.message))?;
+                written.push((m.game_move.clone(), String::from("none")));
+                continue;
+            }
+            if game.current_phase() == Phase::Shop {
+                game.confirm_all().map_err(|e| illegal(&e.message))?;
+            }
+            let position = game.position();
+            let side = position.side_to_move();
+            let game_move = position
+                .parse_move(&m.game_move)
+                .map_err(|e| illegal(&e.message))?;
+            let san = position
+                .to_san(&game_move, false)
+                .unwrap_or_else(|| game_move.clone());
+            game.apply_move(game_move.clone())
+                .map_err(|e| illegal(&e.message))?;
+            m.game_move = game_move;
+            written.push((san, side));
+        }
+        Ok((game, written))
+    }
+
+    /// Result tag must agree with finished game.
+    fn check_result(&self, game: &ShuuroGame) -> Result<(), RecordErrorJS> {
+        if game.current_phase() != Phase::Finished {
+            return Ok(());
+        }
+        let outcome = game.position().outcome();
+        let expected = match outcome.winner.as_deref() {
+            Some("white") => "1-0",
+            Some("black") => "0-1",
+            _ => "1/2-1/2",
+        };
+        match self.tag("Result") {
+            Some(result) if result != "*" && result != expected => Err(RecordErrorJS::new(
+                RecordErrorKind::Result,
+                &format!("game ended with '{}', but result is '{}'", expected, result),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Section where move belongs.
+pub(crate) fn section(game_move: &str) -> &'static str {
+    if game_move.starts_with('+') {
+        SHOP
+    } else if game_move.contains('@') {
+        DEPLOY
+    } else {
+        FIGHT
+    }
+}
+
+pub(crate) fn tag_line(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{} \"{}\"]\n", name, value)
+}
+
+fn parse_tag(line: &str) -> Result<(String, String), RecordErrorJS> {
+    let error = || RecordErrorJS::new(RecordErrorKind::Tag, &format!("invalid tag '{}'", line));
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(error)?;
+    let (name, value) = inner.split_once(' ').ok_or_else(error)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(error)?;
+    let value = value.replace("\\\"", "\"").replace("\\\\", "\\");
+    Ok((String::from(name), value))
+}
+
+/// Split move text into moves, section headers, results, comments and parentheses.
+/// Comments keep opening brace.
+fn tokenize(body: &str) -> Result<Vec<String>, RecordErrorJS> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c == '{' {
+            let mut comment = String::from("{");
+            loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(c) => comment.push(c),
+                    None => {
+                        return Err(RecordErrorJS::new(
+                            RecordErrorKind::Comment,
+                            "comment is not closed",
+                        ))
+                    }
+                }
+            }
+            if !token.is_empty() {
+                tokens.push(token.clone());
+                token.clear();
+            }
+            tokens.push(comment);
+        } else if c == '(' || c == ')' {
+            if !token.is_empty() {
+                tokens.push(token.clone());
+                token.clear();
+            }
+            tokens.push(String::from(c));
+        } else if c.is_whitespace() {
+            if !token.is_empty() {
+                tokens.push(token.clone());
+                token.clear();
+            }
+        } else {
+            token.push(c);
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Move without move number, like `e4` from `1.e4`.
+fn strip_number(token: &str) -> &str {
+    let rest = token.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() < token.len() && rest.starts_with('.') {
+        return rest.trim_start_matches('.');
+    }
+    token
+}
+
+/// Join tokens into lines not longer than `LINE_LENGTH`.
+pub(crate) fn wrap(tokens: &[String]) -> String {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + token.len() + 1 > LINE_LENGTH {
+            lines.push(line.clone());
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(token);
+    }
+    lines.push(line);
+    lines.join("\n")
+}
@@ -0,0 +1,245 @@
+use js_sys::Reflect;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+use crate::game::{GameErrorJS, Phase, ShuuroGame};
+use crate::local_position::PieceJS;
+use crate::position::{pieces_map, plinths_map};
+use crate::record::ShuuroRecord;
+use crate::types::{ReplayPositionValue, StringList};
+
+/// Plies between cached games. Seeking plays at most this many moves.
+/// Cached games have no undo history, so they keep only position.
+const SNAPSHOT_INTERVAL: usize = 16;
+
+/// Board at one ply of replay.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayPosition {
+    pub ply: usize,
+    pub phase: Phase,
+    pub sfen: String,
+    pub side_to_move: String,
+    pub last_move: Option<String>,
+    pub pieces: HashMap<String, PieceJS>,
+    pub plinths: Vec<String>,
+}
+
+/// Class for reviewing finished game. Ply 0 is position before first purchase.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct ShuuroReplay {
+    variant: String,
+    moves: Vec<String>,
+    snapshots: Vec<ShuuroGame>,
+    current: ShuuroGame,
+    ply: usize,
+}
+
+#[wasm_bindgen]
+impl ShuuroReplay {
+    #[wasm_bindgen(constructor)]
+    pub fn new(variant: &str) -> Self {
+        let game = ShuuroGame::new(variant);
+        Self {
+            variant: String::from(variant),
+            moves: vec![],
+            snapshots: vec![game.clone()],
+            current: game,
+            ply: 0,
+        }
+    }
+
+    /// Replay for every move in record.
+    #[wasm_bindgen]
+    pub fn from_record(record: &ShuuroRecord) -> Result<ShuuroReplay, JsValue> {
+        Self::with_record(record).map_err(|e| e.to_js())
+    }
+
+    /// Set starting board with plinths. Loaded moves are played again.
+    #[wasm_bindgen]
+    pub fn set_start_sfen(&mut self, sfen: &str) -> Result<(), JsValue> {
+        self.set_start(sfen).map_err(|e| e.to_js())
+    }
+
+    /// Load purchases, placements and fight moves in sfen format.
+    /// Throws `GameErrorJS` for first illegal move.
+    #[wasm_bindgen]
+    pub fn load(&mut self, moves: StringList) -> Result<(), JsValue> {
+        let moves: Vec<String> = serde_wasm_bindgen::from_value(moves.into())?;
+        self.load_moves(moves).map_err(|e| e.to_js())
+    }
+
+    /// Number of loaded moves.
+    #[wasm_bindgen]
+    pub fn plies(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Current ply.
+    #[wasm_bindgen]
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Jump to ply. Returns false if ply is after last move.
+    /// Moving forward plays moves on current game, without copying it.
+    #[wasm_bindgen]
+    pub fn goto(&mut self, ply: usize) -> bool {
+        if ply > self.moves.len() {
+            return false;
+        }
+        let cached = ply / SNAPSHOT_INTERVAL * SNAPSHOT_INTERVAL;
+        let from = if self.ply <= ply && self.ply >= cached {
+            self.ply
+        } else {
+            self.current = self.snapshots[cached / SNAPSHOT_INTERVAL].clone();
+            cached
+        };
+        for m in self.moves[from..ply].iter() {
+            if play(&mut self.current, m).is_err() {
+                return false;
+            }
+        }
+        self.ply = ply;
+        true
+    }
+
+    #[wasm_bindgen(js_name = next)]
+    pub fn next_ply(&mut self) -> bool {
+        self.goto(self.ply + 1)
+    }
+
+    #[wasm_bindgen(js_name = prev)]
+    pub fn prev_ply(&mut self) -> bool {
+        self.ply > 0 && self.goto(self.ply - 1)
+    }
+
+    /// Board at ply with sfen, pieces and plinths, or null if ply is after last move.
+    #[wasm_bindgen]
+    pub fn position_at(&self, ply: usize) -> ReplayPositionValue {
+        match self.position(ply) {
+            Some(position) => position_js(position).unchecked_into(),
+            None => JsValue::NULL.unchecked_into(),
+        }
+    }
+
+    /// Board at current ply.
+    #[wasm_bindgen]
+    pub fn current_position(&self) -> ReplayPositionValue {
+        self.position_at(self.ply)
+    }
+}
+
+impl ShuuroReplay {
+    /// Start position is taken from `Sfen` tag, or from `Plinths` tag
+    /// when game has shop, same as `ShuuroRecord::replay`.
+    pub fn with_record(record: &ShuuroRecord) -> Result<Self, GameErrorJS> {
+        let mut replay = Self::new(&record.variant());
+        match (record.tag("Sfen"), record.tag("Plinths")) {
+            (Some(sfen), _) => replay.snapshots[0].set_position(&sfen)?,
+            (None, Some(sfen)) => replay.snapshots[0].set_start(&sfen)?,
+            _ => (),
+        }
+        let moves = record.record_moves().iter();
+        replay.load_moves(moves.map(|m| m.game_move.clone()).collect())?;
+        Ok(replay)
+    }
+
+    pub fn set_start(&mut self, sfen: &str) -> Result<(), GameErrorJS> {
+        let mut replay = Self::new(&self.variant);
+        replay.snapshots[0].set_start(sfen)?;
+        replay.load_moves(self.moves.clone())?;
+        *self = replay;
+        Ok(())
+    }
+
+    /// Play every move once and cache game after every `SNAPSHOT_INTERVAL` plies.
+    /// Replay is at ply 0 after loading.
+    pub fn load_moves(&mut self, moves: Vec<String>) -> Result<(), GameErrorJS> {
+        let mut game = self.snapshots[0].clone();
+        let mut snapshots = vec![game.clone()];
+        for (ply, m) in moves.iter().enumerate() {
+            play(&mut game, m).map_err(|e| {
+                GameErrorJS::new(e.kind, &format!("move {} '{}': {}", ply + 1, m, e.message))
+            })?;
+            if (ply + 1) % SNAPSHOT_INTERVAL == 0 {
+                snapshots.push(game.clone());
+            }
+        }
+        self.current = snapshots[0].clone();
+        self.snapshots = snapshots;
+        self.moves = moves;
+        self.ply = 0;
+        Ok(())
+    }
+
+    pub fn moves(&self) -> &[String] {
+        &self.moves
+    }
+
+    /// Game at current ply.
+    pub fn game(&self) -> &ShuuroGame {
+        &self.current
+    }
+
+    pub fn position(&self, ply: usize) -> Option<ReplayPosition> {
+        if ply == self.ply {
+            return Some(self.position_of(&self.current, ply));
+        }
+        let game = self.game_at(ply)?;
+        Some(self.position_of(&game, ply))
+    }
+
+    fn position_of(&self, game: &ShuuroGame, ply: usize) -> ReplayPosition {
+        let position = game.position();
+        ReplayPosition {
+            ply,
+            phase: game.current_phase(),
+            sfen: position.generate_sfen(),
+            side_to_move: game.side_to_move(),
+            last_move: ply.checked_sub(1).map(|i| self.moves[i].clone()),
+            pieces: position.map_pieces(),
+            plinths: position.map_plinths(),
+        }
+    }
+
+    /// Game at ply, played from closest cached game.
+    fn game_at(&self, ply: usize) -> Option<ShuuroGame> {
+        if ply > self.moves.len() {
+            return None;
+        }
+        let cached = ply / SNAPSHOT_INTERVAL * SNAPSHOT_INTERVAL;
+        let (mut game, from) = if self.ply <= ply && self.ply >= cached {
+            (self.current.clone(), self.ply)
+        } else {
+            (self.snapshots[cached / SNAPSHOT_INTERVAL].clone(), cached)
+        };
+        for m in self.moves[from..ply].iter() {
+            play(&mut game, m).ok()?;
+        }
+        Some(game)
+    }
+}
+
+/// Play move for any phase. Hands are confirmed before first placement.
+/// Replay never takes moves back, so undo history is not kept.
+fn play(game: &mut ShuuroGame, game_move: &str) -> Result<(), GameErrorJS> {
+    if game.current_phase() == Phase::Shop && !game_move.starts_with('+') {
+        game.confirm_all()?;
+    }
+    game.apply_move(String::from(game_move))?;
+    game.clear_undo();
+    Ok(())
+}
+
+fn position_js(position: ReplayPosition) -> JsValue {
+    let pieces = pieces_map(position.pieces.clone());
+    let plinths = plinths_map(position.plinths.clone());
+    let value = serde_wasm_bindgen::to_value(&position).unwrap_or(JsValue::NULL);
+    if value.is_object() {
+        let _ = Reflect::set(&value, &JsValue::from_str("pieces"), &pieces);
+        let _ = Reflect::set(&value, &JsValue::from_str("plinths"), &plinths);
+    }
+    value
+}
@@ -0,0 +1,451 @@
+use serde::{Deserialize, Serialize};
+use shuuro::{
+    attacks::Attacks, bitboard::BitBoard, piece_type::PieceTypeIter, position::Position, Color,
+    PieceType, Square, Variant,
+};
+use std::{collections::HashMap, hash::Hash};
+use wasm_bindgen::JsValue;
+
+use crate::local_position::{fight_move, opponent, LocalPosition};
+use crate::outcome::MOVE_LIMIT;
+use crate::selection::piece_price;
+
+/// Score of checkmate. Mate in n plies is `MATE - n`.
+pub const MATE: i32 = 100_000;
+
+/// Captures searched after depth is reached.
+const QUIESCENCE_DEPTH: u8 = 8;
+
+/// How often time limit is checked.
+const TIME_CHECK: u64 = 1024;
+
+/// Limits for `best_move`. Zero means no limit.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub depth: u8,
+    pub nodes: u64,
+    pub time: f64,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            depth: 4,
+            nodes: 0,
+            time: 0.0,
+        }
+    }
+}
+
+/// This represents search error thrown to JS.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchErrorJS {
+    pub message: String,
+}
+
+impl SearchErrorJS {
+    pub fn new(message: &str) -> Self {
+        Self {
+            message: String::from(message),
+        }
+    }
+
+    pub fn to_js(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self).unwrap_or_else(|_| JsValue::from_str(&self.message))
+    }
+}
+
+/// Options from JS. Missing fields get default value, wrong types are error.
+pub fn parse_options(value: JsValue) -> Result<SearchOptions, SearchErrorJS> {
+    if value.is_undefined() || value.is_null() {
+        return Ok(SearchOptions::default());
+    }
+    serde_wasm_bindgen::from_value(value)
+        .map_err(|e| SearchErrorJS::new(&format!("invalid search options: {}", e)))
+}
+
+/// This represents result of search.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub best_move: Option<String>,
+    pub score: i32,
+    pub depth: u8,
+    pub nodes: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone)]
+struct Entry {
+    depth: u8,
+    score: i32,
+    bound: Bound,
+    best_move: Option<String>,
+}
+
+/// Move with its squares for incremental hash.
+/// Resets is true for capture or pawn move, which start halfmove counter again.
+struct SearchMove<S> {
+    game_move: String,
+    from: S,
+    to: S,
+    resets: bool,
+}
+
+/// State shared by all nodes of one search.
+/// History has hashes of fight positions before current node.
+pub(crate) struct SearchContext {
+    options: SearchOptions,
+    values: [i32; 10],
+    start: f64,
+    nodes: u64,
+    stopped: bool,
+    tt: HashMap<u64, Entry>,
+    history: Vec<u64>,
+}
+
+impl SearchContext {
+    pub(crate) fn new(options: SearchOptions, variant: Variant) -> Self {
+        Self {
+            options,
+            values: piece_values(variant),
+            start: now(),
+            nodes: 0,
+            stopped: false,
+            tt: HashMap::new(),
+            history: vec![],
+        }
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+        if self.options.nodes > 0 && self.nodes >= self.options.nodes {
+            self.stopped = true;
+        } else if self.options.time > 0.0
+            && self.nodes % TIME_CHECK == 0
+            && now() - self.start >= self.options.time
+        {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+}
+
+/// Value of every piece type, taken from shop prices.
+pub fn piece_values(variant: Variant) -> [i32; 10] {
+    let mut values = [0; 10];
+    for piece_type in PieceTypeIter::default() {
+        if piece_type == PieceType::King || piece_type == PieceType::Plinth {
+            continue;
+        }
+        values[piece_type.index()] = piece_price(variant, piece_type);
+    }
+    values
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> f64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64() * 1000.0)
+        .unwrap_or(0.0)
+}
+
+impl<S, B, A, P> LocalPosition<S, B, A, P>
+where
+    S: Square + Hash,
+    B: BitBoard<S>,
+    A: Attacks<S, B>,
+    P: Position<S, B, A> + Clone,
+{
+    /// Search best move for side to move with iterative deepening.
+    /// Repeated positions and move limit are scored as draw.
+    pub fn best_move(&self, options: SearchOptions) -> SearchResult {
+        let mut ctx = SearchContext::new(options, self.state.variant());
+        ctx.history = self.fight_hashes();
+        ctx.history.pop();
+        let mut result = SearchResult::default();
+        for depth in 1..=options.depth.max(1) {
+            let (score, best_move) =
+                Self::search_root(&self.state, self.hash, depth, self.halfmoves(), &mut ctx);
+            if ctx.stopped && result.best_move.is_some() {
+                break;
+            }
+            result = SearchResult {
+                best_move,
+                score,
+                depth,
+                nodes: ctx.nodes,
+            };
+            if ctx.stopped || score.abs() >= MATE - i32::from(depth) {
+                break;
+            }
+        }
+        result.nodes = ctx.nodes;
+        result
+    }
+
+    fn search_root(
+        state: &P,
+        key: u64,
+        depth: u8,
+        halfmoves: u16,
+        ctx: &mut SearchContext,
+    ) -> (i32, Option<String>) {
+        let tt_move = ctx.tt.get(&key).and_then(|e| e.best_move.clone());
+        let moves = Self::ordered_moves(state, &ctx.values, tt_move, false);
+        if moves.is_empty() {
+            return (Self::no_moves_score(state, 0), None);
+        }
+        let mut alpha = -MATE - 1;
+        let beta = MATE + 1;
+        let mut best_move = moves.first().map(|m| m.game_move.clone());
+        ctx.history.push(key);
+        for m in moves {
+            let mut next = state.clone();
+            if next.play(&m.game_move).is_err() {
+                continue;
+            }
+            let child = key ^ Self::zobrist_delta(state, &next, &[m.from, m.to]);
+            let halfmoves = if m.resets { 0 } else { halfmoves + 1 };
+            let score =
+                -Self::alpha_beta(&next, child, depth - 1, -beta, -alpha, 1, halfmoves, ctx);
+            if ctx.stopped {
+                break;
+            }
+            if score > alpha {
+                alpha = score;
+                best_move = Some(m.game_move);
+            }
+        }
+        ctx.history.pop();
+        if !ctx.stopped {
+            ctx.tt.insert(
+                key,
+                Entry {
+                    depth,
+                    score: alpha,
+                    bound: Bound::Exact,
+                    best_move: best_move.clone(),
+                },
+            );
+        }
+        (alpha, best_move)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn alpha_beta(
+        state: &P,
+        key: u64,
+        depth: u8,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+        halfmoves: u16,
+        ctx: &mut SearchContext,
+    ) -> i32 {
+        if ctx.should_stop() {
+            return 0;
+        }
+        if ctx.history.contains(&key) {
+            return 0;
+        }
+        if depth == 0 {
+            return Self::quiescence(state, alpha, beta, QUIESCENCE_DEPTH, ctx);
+        }
+        ctx.nodes += 1;
+        let mut tt_move = None;
+        if let Some(entry) = ctx.tt.get(&key) {
+            if entry.depth >= depth {
+                let cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.score >= beta,
+                    Bound::Upper => entry.score <= alpha,
+                };
+                if cutoff {
+                    return entry.score;
+                }
+            }
+            tt_move = entry.best_move.clone();
+        }
+        let moves = Self::ordered_moves(state, &ctx.values, tt_move, false);
+        if moves.is_empty() {
+            return Self::no_moves_score(state, ply);
+        }
+        if halfmoves >= MOVE_LIMIT {
+            return 0;
+        }
+        let original_alpha = alpha;
+        let mut best_move = None;
+        ctx.history.push(key);
+        for m in moves {
+            let mut next = state.clone();
+            if next.play(&m.game_move).is_err() {
+                continue;
+            }
+            let child = key ^ Self::zobrist_delta(state, &next, &[m.from, m.to]);
+            let halfmoves = if m.resets { 0 } else { halfmoves + 1 };
+            let score = -Self::alpha_beta(
+                &next,
+                child,
+                depth - 1,
+                -beta,
+                -alpha,
+                ply + 1,
+                halfmoves,
+                ctx,
+            );
+            if ctx.stopped {
+                ctx.history.pop();
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                best_move = Some(m.game_move);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        ctx.history.pop();
+        if alpha.abs() < MATE - 1000 {
+            let bound = if alpha >= beta {
+                Bound::Lower
+            } else if alpha <= original_alpha {
+                Bound::Upper
+            } else {
+                Bound::Exact
+            };
+            let entry = Entry {
+                depth,
+                score: alpha,
+                bound,
+                best_move,
+            };
+            ctx.tt.insert(key, entry);
+        }
+        alpha
+    }
+
+    fn quiescence(state: &P, mut alpha: i32, beta: i32, depth: u8, ctx: &mut SearchContext) -> i32 {
+        if ctx.should_stop() {
+            return 0;
+        }
+        ctx.nodes += 1;
+        let stand_pat = Self::material(state, &ctx.values);
+        if stand_pat >= beta || depth == 0 {
+            return stand_pat;
+        }
+        if stand_pat > alpha {
+            alpha = stand_pat;
+        }
+        for m in Self::ordered_moves(state, &ctx.values, None, true) {
+            let mut next = state.clone();
+            if next.play(&m.game_move).is_err() {
+                continue;
+            }
+            let score = -Self::quiescence(&next, -beta, -alpha, depth - 1, ctx);
+            if ctx.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+        alpha
+    }
+
+    /// Moves sorted by most valuable victim, least valuable attacker.
+    fn ordered_moves(
+        state: &P,
+        values: &[i32; 10],
+        tt_move: Option<String>,
+        captures_only: bool,
+    ) -> Vec<SearchMove<S>> {
+        let stm = state.side_to_move();
+        let mut moves = vec![];
+        for m in state.legal_moves(stm) {
+            let (attacker, pawn) = match state.piece_at(m.0) {
+                Some(piece) => (
+                    values[piece.piece_type.index()],
+                    piece.piece_type == PieceType::Pawn,
+                ),
+                None => (0, false),
+            };
+            let from = m.0;
+            let from_name = from.to_string();
+            for to in m.1 {
+                let victim = match state.piece_at(to) {
+                    Some(piece) if piece.color == opponent(stm) => {
+                        Some(values[piece.piece_type.index()])
+                    }
+                    _ => None,
+                };
+                if captures_only && victim.is_none() {
+                    continue;
+                }
+                let order = victim.map(|v| v * 16 - attacker).unwrap_or(i32::MIN);
+                let resets = pawn || victim.is_some();
+                let game_move = fight_move(&from_name, &to.to_string());
+                let m = SearchMove {
+                    game_move,
+                    from,
+                    to,
+                    resets,
+                };
+                moves.push((m, order));
+            }
+        }
+        moves.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| a.0.game_move.cmp(&b.0.game_move))
+        });
+        let mut moves: Vec<SearchMove<S>> = moves.into_iter().map(|m| m.0).collect();
+        if let Some(tt_move) = tt_move {
+            if let Some(index) = moves.iter().position(|m| m.game_move == tt_move) {
+                let m = moves.remove(index);
+                moves.insert(0, m);
+            }
+        }
+        moves
+    }
+
+    /// Material balance for side to move.
+    pub(crate) fn material(state: &P, values: &[i32; 10]) -> i32 {
+        let mut score = 0;
+        for color in [Color::White, Color::Black] {
+            let sign = if color == state.side_to_move() { 1 } else { -1 };
+            for sq in state.player_bb(color) {
+                if let Some(piece) = state.piece_at(sq) {
+                    score += sign * values[piece.piece_type.index()];
+                }
+            }
+        }
+        score
+    }
+
+    fn no_moves_score(state: &P, ply: i32) -> i32 {
+        if state.in_check(state.side_to_move()) {
+            -MATE + ply
+        } else {
+            0
+        }
+    }
+}
@@ -1,9 +1,13 @@
-use js_sys::{Array, Uint8Array};
+use js_sys::Array;
 
 use shuuro::{
     piece_type::PieceTypeIter, shuuro12::square12::Square12, Color, Move, Piece, PieceType, Variant,
 };
-use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+use std::str::FromStr;
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+
+use crate::buyer::{plan_army, ShopStyle};
+use crate::types::{ShopItem, ShopItems, StringList};
 
 /// Class for ShuuroShop
 /// Generics in Shop are not that important.
@@ -33,16 +37,13 @@ impl ShuuroShop {
         self.shuuro.variant().to_string()
     }
 
-    /// Buying piece. game_move is in this format `+P`. Returns Uint8Array
+    /// Buying piece. game_move is in this format `+P`. Returns shop items for that player.
     #[wasm_bindgen]
-    pub fn buy(&mut self, game_move: String) -> Uint8Array {
-        if let Some(game_move) = Move::from_sfen(game_move.as_str()) {
-            if let Move::Select { piece } = game_move {
-                self.shuuro.play(game_move);
-                return self.js_shop_items(&piece.color);
-            }
+    pub fn buy(&mut self, game_move: String) -> ShopItems {
+        match self.select(&game_move) {
+            Some(color) => self.js_shop_items(&color),
+            None => Array::new().unchecked_into(),
         }
-        Uint8Array::new_with_length(9)
     }
 
     /// Confirm players hand. s is color. It can be 'w' or 'b'.
@@ -87,42 +88,24 @@ impl ShuuroShop {
 
     /// Count all items for selected player.
     #[wasm_bindgen]
-    pub fn shop_items(&self, s: char) -> Uint8Array {
+    pub fn shop_items(&self, s: char) -> ShopItems {
         let color = Color::from_char(s);
         if let Some(c) = color {
-            if let Color::NoColor = c {
-                return Uint8Array::new_with_length(9);
-            } else {
+            if c != Color::NoColor {
                 return self.js_shop_items(&c);
             }
         }
-        Uint8Array::new_with_length(8)
-    }
-
-    fn js_shop_items(&self, color: &Color) -> Uint8Array {
-        let array = Uint8Array::new_with_length(9);
-        let mut current_state: [u8; 9] = [1, 0, 0, 0, 0, 0, 0, 0, 0];
-        let iterator = PieceTypeIter::default();
-        for i in iterator {
-            if !self.shuuro.variant().can_select(&i) {
-                continue;
-            } else if i == PieceType::King {
-                array.set_index(0, current_state[0]);
-            } else if i == PieceType::Plinth {
-                continue;
-            }
-            if i.index() != 0 || i.index() != 9 {
-                let piece = Piece {
-                    piece_type: i,
-                    color: *color,
-                };
-                let index = i.index();
-                let current = self.shuuro.get(piece);
-                current_state[index] = current;
-                array.set_index(index as u32, current);
+        Array::new().unchecked_into()
+    }
+
+    fn js_shop_items(&self, color: &Color) -> ShopItems {
+        let items = Array::new();
+        for item in shop_items(&self.shuuro, *color) {
+            if let Ok(item) = serde_wasm_bindgen::to_value(&item) {
+                items.push(&item);
             }
         }
-        array
+        items.unchecked_into()
     }
 
     fn _js_shop_index(&self, index: usize) -> usize {
@@ -153,14 +136,110 @@ impl ShuuroShop {
     #[wasm_bindgen]
     pub fn history(&self) -> Array {
         let ar = Array::new();
-        let history = self.shuuro.get_sfen_history(&Color::NoColor);
-        for m in history {
-            // let t = Array::new();
-            // t.push(&JsValue::from_str(m.0.as_str()));
-            // t.push(&JsValue::from(m.1));
-            // ar.push(&JsValue::from(t));
-            ar.push(&JsValue::from_str(m.0.as_str()));
+        for m in self.sfen_history() {
+            ar.push(&JsValue::from_str(m.as_str()));
         }
         ar
     }
+
+    /// Computer purchases for player in `+X` format. Style is "balanced", "aggressive"
+    /// or "fairyHeavy", other styles throw. Shop is not changed,
+    /// moves should be played with `buy`.
+    #[wasm_bindgen]
+    pub fn plan_army(&self, s: char, style: &str, seed: u32) -> Result<StringList, JsValue> {
+        let style = ShopStyle::from_str(style).map_err(|e| JsValue::from_str(&e))?;
+        let ar = Array::new();
+        if let Some(color) = Color::from_char(s) {
+            for m in self.army(color, style, seed as u64) {
+                ar.push(&JsValue::from_str(&m));
+            }
+        }
+        Ok(ar.unchecked_into())
+    }
+}
+
+impl ShuuroShop {
+    /// Buying piece without JS types. Returns color of buyer.
+    pub fn select(&mut self, game_move: &str) -> Option<Color> {
+        if let Some(game_move) = Move::from_sfen(game_move) {
+            if let Move::Select { piece } = game_move {
+                self.shuuro.play(game_move);
+                return Some(piece.color);
+            }
+        }
+        None
+    }
+
+    /// Native shop used by this class.
+    pub fn selection(&self) -> &shuuro::Selection<Square12> {
+        &self.shuuro
+    }
+
+    /// All moves for both players in sfen format.
+    pub fn sfen_history(&self) -> Vec<String> {
+        let history = self.shuuro.get_sfen_history(&Color::NoColor);
+        history.iter().map(|m| m.0.clone()).collect()
+    }
+
+    /// Computer purchases for player, without JS types.
+    pub fn army(&self, color: Color, style: ShopStyle, seed: u64) -> Vec<String> {
+        match color {
+            Color::NoColor => vec![],
+            _ => plan_army(&self.shuuro, color, style, seed),
+        }
+    }
+
+    /// Items for selected player, without JS types.
+    pub fn items(&self, color: Color) -> Vec<ShopItem> {
+        shop_items(&self.shuuro, color)
+    }
+}
+
+/// Every piece that can be bought in variant, with count and price.
+pub fn shop_items(shop: &shuuro::Selection<Square12>, color: Color) -> Vec<ShopItem> {
+    let mut items = vec![];
+    let variant = shop.variant();
+    let iterator = PieceTypeIter::default();
+    for i in iterator {
+        if i == PieceType::Plinth || (i != PieceType::King && !variant.can_select(&i)) {
+            continue;
+        }
+        let piece = Piece {
+            piece_type: i,
+            color,
+        };
+        let mut count = shop.get(piece);
+        if i == PieceType::King {
+            count = count.max(1);
+        }
+        let mut role = piece.to_string().to_lowercase();
+        role.push_str("-piece");
+        items.push(ShopItem {
+            role,
+            color: String::from(color_name(&color)),
+            count,
+            price: piece_price(variant, i),
+        });
+    }
+    items
+}
+
+/// Price of piece in shop. It is measured by buying one piece in empty shop.
+pub fn piece_price(variant: Variant, piece_type: PieceType) -> i32 {
+    let mut shop = shuuro::Selection::<Square12>::default();
+    shop.update_variant(variant);
+    let piece = Piece {
+        piece_type,
+        color: Color::White,
+    };
+    let before = shop.credit(Color::White);
+    shop.play(Move::Select { piece });
+    before - shop.credit(Color::White)
+}
+
+fn color_name(color: &Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        _ => "black",
+    }
 }
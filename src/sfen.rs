@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use shuuro::{Piece, Variant};
+use wasm_bindgen::JsValue;
+
+/// Reason why sfen was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SfenErrorKind {
+    MissingDataFields,
+    RankCount,
+    RankLength,
+    UnknownPiece,
+    InvalidHand,
+    SideToMove,
+    PlyCounter,
+    IllegalBoardState,
+}
+
+/// This represents sfen error thrown to JS.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SfenErrorJS {
+    pub kind: SfenErrorKind,
+    pub message: String,
+}
+
+impl SfenErrorJS {
+    pub fn new(kind: SfenErrorKind, message: &str) -> Self {
+        Self {
+            kind,
+            message: String::from(message),
+        }
+    }
+
+    pub fn to_js(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self).unwrap_or_else(|_| JsValue::from_str(&self.message))
+    }
+}
+
+/// Number of files and ranks for variant.
+pub fn board_size(variant: Variant) -> usize {
+    match variant {
+        Variant::Standard | Variant::StandardFairy => 8,
+        Variant::ShuuroMini | Variant::ShuuroMiniFairy => 6,
+        _ => 12,
+    }
+}
+
+/// Number of ranks in sfen board.
+pub fn sfen_size(sfen: &str) -> usize {
+    match sfen.split_whitespace().next() {
+        Some(board) => board.split('/').count(),
+        None => 0,
+    }
+}
+
+/// Check sfen before it reaches `Position::set_sfen`.
+pub fn validate_sfen(s: &str, size: usize) -> Result<(), SfenErrorJS> {
+    let parts: Vec<&str> = s.split_whitespace().collect();
+    if parts.len() != 4 {
+        return Err(SfenErrorJS::new(
+            SfenErrorKind::MissingDataFields,
+            "sfen must have board, side to move, hand and ply",
+        ));
+    }
+    validate_board(parts[0], size)?;
+    if parts[1] != "w" && parts[1] != "b" {
+        return Err(SfenErrorJS::new(
+            SfenErrorKind::SideToMove,
+            "side to move must be 'w' or 'b'",
+        ));
+    }
+    validate_hand(parts[2])?;
+    if parts[3].parse::<u16>().is_err() {
+        return Err(SfenErrorJS::new(
+            SfenErrorKind::PlyCounter,
+            "ply must be a positive number",
+        ));
+    }
+    Ok(())
+}
+
+fn validate_board(board: &str, size: usize) -> Result<(), SfenErrorJS> {
+    let ranks: Vec<&str> = board.split('/').collect();
+    if ranks.len() != size {
+        return Err(SfenErrorJS::new(
+            SfenErrorKind::RankCount,
+            &format!("expected {} ranks, found {}", size, ranks.len()),
+        ));
+    }
+    for rank in ranks {
+        let mut files = 0;
+        let mut chars = rank.chars();
+        while let Some(c) = chars.next() {
+            if let Some(n) = c.to_digit(10) {
+                files += n as usize;
+            } else if c == 'L' {
+                match chars.next() {
+                    Some('0') => (),
+                    Some(p) if is_piece(p) => (),
+                    _ => {
+                        return Err(SfenErrorJS::new(
+                            SfenErrorKind::UnknownPiece,
+                            &format!("plinth in rank '{}' has no piece or '0'", rank),
+                        ))
+                    }
+                }
+                files += 1;
+            } else if is_piece(c) {
+                files += 1;
+            } else {
+                return Err(SfenErrorJS::new(
+                    SfenErrorKind::UnknownPiece,
+                    &format!("unknown piece '{}'", c),
+                ));
+            }
+        }
+        if files != size {
+            return Err(SfenErrorJS::new(
+                SfenErrorKind::RankLength,
+                &format!("rank '{}' has {} files, expected {}", rank, files, size),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_hand(hand: &str) -> Result<(), SfenErrorJS> {
+    if hand == "-" {
+        return Ok(());
+    }
+    let mut count = String::new();
+    for c in hand.chars() {
+        if c.is_ascii_digit() {
+            count.push(c);
+        } else if is_piece(c) {
+            if count.parse::<u8>().map(|n| n == 0).unwrap_or(false) {
+                return Err(SfenErrorJS::new(
+                    SfenErrorKind::InvalidHand,
+                    &format!("piece '{}' has zero count in hand", c),
+                ));
+            }
+            count.clear();
+        } else {
+            return Err(SfenErrorJS::new(
+                SfenErrorKind::InvalidHand,
+                &format!("unknown piece '{}' in hand", c),
+            ));
+        }
+    }
+    if !count.is_empty() {
+        return Err(SfenErrorJS::new(
+            SfenErrorKind::InvalidHand,
+            "hand ends with count but no piece",
+        ));
+    }
+    Ok(())
+}
+
+fn is_piece(c: char) -> bool {
+    c != 'L' && c != 'l' && Piece::from_sfen(c).is_some()
+}
+
+/// File (from 0) and rank (from 1) of square name like `c10`.
+pub fn square_coords(name: &str) -> Option<(usize, usize)> {
+    let mut chars = name.chars();
+    let file = chars.next()?;
+    if !file.is_ascii_lowercase() {
+        return None;
+    }
+    let rank = chars.as_str().parse::<usize>().ok()?;
+    Some((file as usize - 'a' as usize, rank))
+}
+
+/// Square name from file (from 0) and rank (from 1).
+pub fn square_name(file: usize, rank: usize) -> String {
+    format!("{}{}", (b'a' + file as u8) as char, rank)
+}
+
+/// Sfen with other piece on square. Square must already have piece,
+/// plinth under it is kept.
+pub fn replace_piece(sfen: &str, square: (usize, usize), piece: char) -> Option<String> {
+    let (board, rest) = sfen.split_once(' ')?;
+    let mut ranks: Vec<String> = board.split('/').map(String::from).collect();
+    let index = ranks.len().checked_sub(square.1)?;
+    let rank = ranks.get_mut(index)?;
+    let mut replaced = String::new();
+    let mut file = 0;
+    let mut found = false;
+    let mut chars = rank.chars();
+    while let Some(c) = chars.next() {
+        if let Some(n) = c.to_digit(10) {
+            replaced.push(c);
+            file += n as usize;
+            continue;
+        }
+        let mut current = c;
+        if c == 'L' {
+            replaced.push(c);
+            current = chars.next()?;
+        }
+        if file == square.0 && current != '0' {
+            replaced.push(piece);
+            found = true;
+        } else {
+            replaced.push(current);
+        }
+        file += 1;
+    }
+    if !found {
+        return None;
+    }
+    *rank = replaced;
+    Some(format!("{} {}", ranks.join("/"), rest))
+}
@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &str = r#"
+export type File = "a" | "b" | "c" | "d" | "e" | "f" | "g" | "h" | "i" | "j" | "k" | "l";
+export type Rank = 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9 | 10 | 11 | 12;
+export type SquareName = `${File}${Rank}`;
+export type PieceColor = "white" | "black";
+export type PieceLetter = "K" | "Q" | "R" | "B" | "N" | "P" | "C" | "A" | "G"
+  | "k" | "q" | "r" | "b" | "n" | "p" | "c" | "a" | "g";
+export type PlaceKey = `${Uppercase<PieceLetter>}@`;
+
+export interface PieceJS {
+  role: string;
+  color: PieceColor;
+}
+
+export interface ShopItem {
+  role: string;
+  color: PieceColor;
+  count: number;
+  price: number;
+}
+
+export type PiecesMap = Map<SquareName, PieceJS>;
+export type PlinthsMap = Map<SquareName, PieceJS>;
+export type MovesMap = Map<SquareName, SquareName[]>;
+export type PlaceMovesMap = Map<PlaceKey, SquareName[]>;
+export type DivideMap = Map<string, bigint>;
+
+export interface DestinationJS {
+  square: SquareName;
+  promotion: PieceLetter[];
+}
+
+export type DestinationsMap = Map<SquareName, DestinationJS[]>;
+
+export interface SfenErrorJS {
+  kind: "missingDataFields" | "rankCount" | "rankLength" | "unknownPiece"
+    | "invalidHand" | "sideToMove" | "plyCounter" | "illegalBoardState";
+  message: string;
+}
+
+export interface GameErrorJS {
+  kind: "wrongPhase" | "alreadyConfirmed" | "illegalMove" | "gameOver" | "invalidSfen"
+    | "timeout";
+  message: string;
+}
+
+export type Phase = "shop" | "deploy" | "fight" | "finished";
+
+export interface PhaseControl {
+  kind: "suddenDeath" | "fischer" | "bronstein" | "delay";
+  base: number;
+  increment: number;
+}
+
+export interface TimeControl {
+  shop: PhaseControl;
+  deploy: PhaseControl;
+  fight: PhaseControl;
+}
+
+export interface SideClock {
+  remaining: number;
+  started: number | null;
+}
+
+export interface ClockState {
+  control: TimeControl;
+  phase: Phase;
+  white: SideClock;
+  black: SideClock;
+  flagged: PieceColor | null;
+}
+
+export interface ClockView {
+  phase: Phase;
+  white: number;
+  black: number;
+  running: PieceColor[];
+  flagged: PieceColor | null;
+}
+
+export interface MoveParseErrorJS {
+  kind: "syntax" | "illegal" | "ambiguous";
+  message: string;
+  candidates: string[];
+}
+
+export interface RecordErrorJS {
+  kind: "tag" | "section" | "comment" | "illegalMove" | "result";
+  message: string;
+}
+
+export interface SearchErrorJS {
+  message: string;
+}
+
+export interface OutcomeJS {
+  kind: "ongoing" | "check" | "checkmate" | "stalemate" | "repetition"
+    | "moveLimit" | "insufficientMaterial";
+  game_over: boolean;
+  winner: PieceColor | null;
+}
+
+export interface SearchOptions {
+  depth?: number;
+  nodes?: number;
+  time?: number;
+}
+
+export interface SearchResult {
+  best_move: string | null;
+  score: number;
+  depth: number;
+  nodes: number;
+}
+
+export interface Evaluation {
+  score: number;
+  material: number;
+  hand: number;
+  mobility: number;
+  king_safety: number;
+  plinths: number;
+}
+
+export interface ReplayPosition {
+  ply: number;
+  phase: Phase;
+  sfen: string;
+  side_to_move: string;
+  last_move: string | null;
+  pieces: PiecesMap;
+  plinths: PlinthsMap;
+}
+
+export interface AnalysisNode {
+  id: number;
+  game_move: string;
+  san: string;
+  side: string;
+  comment: string | null;
+  parent: number | null;
+  children: number[];
+}
+
+export interface Pin {
+  square: SquareName;
+  pinner: SquareName;
+  ray: SquareName[];
+}
+
+export interface Threats {
+  checkers: SquareName[];
+  check_rays: SquareName[][];
+  blocks: SquareName[];
+  pins: Pin[];
+  discovered: Pin[];
+}
+
+export interface RepetitionJS {
+  count: number;
+  claimable: boolean;
+  automatic: boolean;
+}
+
+export interface RepetitionRule {
+  claim: number;
+  automatic: number;
+}
+
+export interface PremoveJS {
+  game_move: string;
+  played: boolean;
+  outcome: OutcomeJS | null;
+}
+
+export interface MoveResultJS {
+  game_move: string;
+  outcome: OutcomeJS;
+  premove: PremoveJS | null;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "PiecesMap")]
+    pub type PiecesMap;
+
+    #[wasm_bindgen(typescript_type = "PlinthsMap")]
+    pub type PlinthsMap;
+
+    #[wasm_bindgen(typescript_type = "MovesMap")]
+    pub type MovesMap;
+
+    #[wasm_bindgen(typescript_type = "DestinationsMap")]
+    pub type DestinationsMap;
+
+    #[wasm_bindgen(typescript_type = "PlaceMovesMap")]
+    pub type PlaceMovesMap;
+
+    #[wasm_bindgen(typescript_type = "DivideMap")]
+    pub type DivideMap;
+
+    #[wasm_bindgen(typescript_type = "Evaluation")]
+    pub type EvaluationValue;
+
+    #[wasm_bindgen(typescript_type = "string[]")]
+    pub type StringList;
+
+    #[wasm_bindgen(typescript_type = "SquareName[]")]
+    pub type SquareList;
+
+    #[wasm_bindgen(typescript_type = "Threats")]
+    pub type ThreatsValue;
+
+    #[wasm_bindgen(typescript_type = "ShopItem[]")]
+    pub type ShopItems;
+
+    #[wasm_bindgen(typescript_type = "TimeControl")]
+    pub type TimeControlValue;
+
+    #[wasm_bindgen(typescript_type = "ClockView | null")]
+    pub type ClockViewValue;
+
+    #[wasm_bindgen(typescript_type = "ClockState | null")]
+    pub type ClockStateValue;
+
+    #[wasm_bindgen(typescript_type = "RepetitionJS")]
+    pub type RepetitionValue;
+
+    #[wasm_bindgen(typescript_type = "RepetitionRule")]
+    pub type RepetitionRuleValue;
+
+    #[wasm_bindgen(typescript_type = "OutcomeJS")]
+    pub type OutcomeValue;
+
+    #[wasm_bindgen(typescript_type = "MoveResultJS | null")]
+    pub type MoveResultValue;
+
+    #[wasm_bindgen(typescript_type = "SearchOptions")]
+    pub type SearchOptionsValue;
+
+    #[wasm_bindgen(typescript_type = "SearchResult")]
+    pub type SearchResultValue;
+
+    #[wasm_bindgen(typescript_type = "ReplayPosition | null")]
+    pub type ReplayPositionValue;
+
+    #[wasm_bindgen(typescript_type = "AnalysisNode | null")]
+    pub type AnalysisNodeValue;
+}
+
+/// This represents one item in shop.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShopItem {
+    pub role: String,
+    pub color: String,
+    pub count: u8,
+    pub price: i32,
+}
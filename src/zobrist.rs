@@ -0,0 +1,138 @@
+use shuuro::{attacks::Attacks, bitboard::BitBoard, position::Position, Color, Piece, Square};
+use std::{collections::HashMap, hash::Hash};
+
+use crate::local_position::LocalPosition;
+
+/// Keys are made from this seed, so hash is same on client and server.
+const SEED: u64 = 0x5348_5555_524f_5a4b;
+/// Biggest board has 144 squares, smaller boards use first square indexes.
+const SQUARES: u64 = 144;
+const PIECE_TYPES: u64 = 16;
+const HAND_COUNT: u64 = 64;
+
+const PIECES: u64 = 1;
+const PLINTHS: u64 = PIECES + 2 * PIECE_TYPES * SQUARES;
+const HANDS: u64 = PLINTHS + SQUARES;
+
+/// Random number for key index. Index 0 is key for black to move.
+fn key(index: u64) -> u64 {
+    let mut z = SEED.wrapping_add(index.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn color_index(color: Color) -> u64 {
+    match color {
+        Color::White => 0,
+        _ => 1,
+    }
+}
+
+fn piece_key<S: Square>(piece: &Piece, sq: S) -> u64 {
+    let kind = color_index(piece.color) * PIECE_TYPES + piece.piece_type.index() as u64;
+    key(PIECES + kind * SQUARES + sq.index() as u64)
+}
+
+fn plinth_key<S: Square>(sq: S) -> u64 {
+    key(PLINTHS + sq.index() as u64)
+}
+
+fn side_key(color: Color) -> u64 {
+    match color {
+        Color::Black => key(0),
+        _ => 0,
+    }
+}
+
+/// Hand count of piece type is hashed as one key. Empty hand has no key.
+fn hand_key(piece: &Piece, count: u64) -> u64 {
+    if count == 0 {
+        return 0;
+    }
+    let kind = color_index(piece.color) * PIECE_TYPES + piece.piece_type.index() as u64;
+    key(HANDS + kind * HAND_COUNT + count.min(HAND_COUNT - 1))
+}
+
+impl<S, B, A, P> LocalPosition<S, B, A, P>
+where
+    S: Square + Hash,
+    B: BitBoard<S>,
+    A: Attacks<S, B>,
+    P: Position<S, B, A> + Clone,
+{
+    /// 64-bit Zobrist hash of pieces, plinths, hands and side to move.
+    pub fn zobrist(&self) -> u64 {
+        self.hash
+    }
+
+    /// Hash computed from scratch.
+    pub(crate) fn zobrist_of(state: &P) -> u64 {
+        let mut hash = side_key(state.side_to_move()) ^ Self::hands_key(state);
+        for sq in state.player_bb(Color::NoColor) {
+            hash ^= plinth_key(sq);
+        }
+        for color in [Color::White, Color::Black] {
+            for sq in state.player_bb(color) {
+                hash ^= Self::square_key(state, sq);
+            }
+        }
+        hash
+    }
+
+    /// Change of hash after fight move that touched only these squares.
+    /// Hands do not change in fight.
+    pub(crate) fn zobrist_delta(before: &P, after: &P, squares: &[S]) -> u64 {
+        let mut delta = side_key(before.side_to_move()) ^ side_key(after.side_to_move());
+        for sq in squares {
+            delta ^= Self::square_key(before, *sq) ^ Self::square_key(after, *sq);
+        }
+        delta
+    }
+
+    /// Change of hash after piece from hand was placed on square.
+    /// Only hand count of that piece is changed.
+    pub(crate) fn zobrist_place_delta(before: &P, after: &P, piece: Piece, sq: S) -> u64 {
+        let count = |state: &P| Self::hand_count(state, &piece);
+        let mut delta = Self::zobrist_delta(before, after, &[sq]);
+        delta ^= hand_key(&piece, count(before)) ^ hand_key(&piece, count(after));
+        delta
+    }
+
+    fn square_key(state: &P, sq: S) -> u64 {
+        match state.piece_at(sq) {
+            Some(piece) => {
+                let piece = Piece {
+                    piece_type: piece.piece_type,
+                    color: piece.color,
+                };
+                piece_key(&piece, sq)
+            }
+            None => 0,
+        }
+    }
+
+    fn hand_count(state: &P, piece: &Piece) -> u64 {
+        let letter = piece.to_string();
+        state
+            .get_hand(piece.color, true)
+            .matches(letter.as_str())
+            .count() as u64
+    }
+
+    fn hands_key(state: &P) -> u64 {
+        let mut hash = 0;
+        for color in [Color::White, Color::Black] {
+            let mut counts: HashMap<char, u64> = HashMap::new();
+            for c in state.get_hand(color, true).chars() {
+                *counts.entry(c).or_default() += 1;
+            }
+            for (c, count) in counts {
+                if let Some(piece) = Piece::from_sfen(c) {
+                    hash ^= hand_key(&piece, count);
+                }
+            }
+        }
+        hash
+    }
+}
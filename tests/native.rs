@@ -0,0 +1,1176 @@
+//! Test suite that runs with `cargo test`, without browser.
+
+use shuuro::{Color, Variant};
+use shuuro_wasm::game::{GameErrorKind, ShuuroGame};
+use shuuro_wasm::selection::ShuuroShop;
+use shuuro_wasm::sfen::SfenErrorKind;
+use shuuro_wasm::PositionContainer;
+
+const VARIANTS: [&str; 6] = [
+    "standard",
+    "standardFairy",
+    "shuuro",
+    "shuuroFairy",
+    "shuuroMini",
+    "shuuroMiniFairy",
+];
+
+fn position(variant: &str) -> PositionContainer {
+    PositionContainer::new(Variant::from(&variant.to_string()))
+}
+
+/// Place piece on first square of its back rank that accepts it.
+fn place_on_back_rank(game: &mut ShuuroGame, piece: char) {
+    let rank = if piece.is_ascii_uppercase() { 1 } else { 8 };
+    let placed = ('a'..='h').any(|file| {
+        game.apply_move(format!("{}@{}{}", piece, file, rank))
+            .is_ok()
+    });
+    assert!(placed, "{}", piece);
+}
+
+fn error_kind(game: &mut ShuuroGame, game_move: &str) -> GameErrorKind {
+    game.apply_move(String::from(game_move)).unwrap_err().kind
+}
+
+/// Starting fight position for variant.
+fn fight_sfen(variant: &str) -> &'static str {
+    match variant {
+        "standard" | "standardFairy" => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - 1",
+        "shuuroMini" | "shuuroMiniFairy" => "rnbkqn/pppppp/6/6/PPPPPP/RNBKQN w - 1",
+        _ => "57/2rnbqkbnr2/2pppppppp2/57/57/57/57/57/57/2PPPPPPPP2/2RNBQKBNR2/57 w - 1",
+    }
+}
+
+/// Deterministic move for side to move.
+fn first_move(pos: &PositionContainer) -> Option<String> {
+    let color = if pos.side_to_move() == "w" { 0 } else { 1 };
+    let mut moves: Vec<String> = pos
+        .legal_moves(color)
+        .into_iter()
+        .flat_map(|(from, to)| to.into_iter().map(move |to| format!("{}_{}", from, to)))
+        .collect();
+    moves.sort();
+    moves.into_iter().next()
+}
+
+/// Game with purchases, deploy by computer and first moves in fight.
+fn play_game(variant: &str, purchases: &[&str], fight_plies: usize) -> (ShuuroGame, Vec<String>) {
+    use shuuro_wasm::game::Phase;
+    let mut game = ShuuroGame::new(variant);
+    let mut moves = vec![];
+    for m in purchases {
+        game.apply_move(String::from(*m)).unwrap();
+        moves.push(String::from(*m));
+    }
+    game.confirm_all().unwrap();
+    while game.current_phase() == Phase::Deploy {
+        let m = game.position().deploy_move(2, 0).unwrap();
+        game.apply_move(m.clone()).unwrap();
+        moves.push(m);
+    }
+    for _ in 0..fight_plies {
+        let m = first_move(game.position()).unwrap();
+        game.apply_move(m.clone()).unwrap();
+        moves.push(m);
+    }
+    (game, moves)
+}
+
+#[test]
+fn shop_purchase() {
+    for variant in VARIANTS {
+        let mut game = ShuuroGame::new(variant);
+        let credit = game.get_credit('w');
+        game.apply_move(String::from("+Q")).unwrap();
+        assert!(game.get_credit('w') < credit, "{}", variant);
+        assert_eq!(game.get_credit('b'), credit);
+        assert!(game.apply_move(String::from("Q@a1")).is_err());
+        assert_eq!(game.phase(), "shop");
+    }
+}
+
+#[test]
+fn fairy_shop() {
+    for variant in VARIANTS {
+        let mut shop = ShuuroShop::new();
+        shop.change_variant(Variant::from(&variant.to_string()) as u8);
+        let credit = shop.get_credit('w');
+        assert_eq!(shop.select("+G"), Some(Color::White));
+        if variant.ends_with("Fairy") {
+            assert!(shop.get_credit('w') < credit, "{}", variant);
+            assert_eq!(shop.get_piece('G'), 1, "{}", variant);
+        } else {
+            assert_eq!(shop.get_credit('w'), credit, "{}", variant);
+            assert_eq!(shop.get_piece('G'), 0, "{}", variant);
+        }
+        assert_eq!(shop.get_credit('b'), credit);
+        assert_eq!(shop.select("Q@a1"), None);
+    }
+
+    let mut shop = ShuuroShop::new();
+    shop.change_variant(Variant::ShuuroFairy as u8);
+    let mut credits = vec![];
+    for _ in 0..3 {
+        shop.select("+G");
+        credits.push(shop.get_credit('w'));
+    }
+    assert_eq!(credits[2], 660);
+    // third giraffe is over the limit
+    assert_eq!(credits[2], credits[1]);
+    assert!(shop.get_piece('G') > 0);
+}
+
+#[test]
+fn confirm_starts_deploy() {
+    for variant in VARIANTS {
+        let mut game = ShuuroGame::new(variant);
+        game.apply_move(String::from("+Q")).unwrap();
+        game.apply_move(String::from("+r")).unwrap();
+        game.confirm_color('w').unwrap();
+        assert!(game.apply_move(String::from("+P")).is_err());
+        assert_eq!(game.phase(), "shop");
+        game.confirm_color('b').unwrap();
+        assert_eq!(game.phase(), "deploy");
+        assert_ne!(game.side_to_move(), "none");
+        let hand = game.position().count_hand_pieces();
+        for piece in ['K', 'Q', 'k', 'r'] {
+            assert!(hand.contains(piece), "{} {}", variant, hand);
+        }
+    }
+}
+
+#[test]
+fn game_phases() {
+    let mut game = ShuuroGame::new("standard");
+    assert_eq!(game.phase(), "shop");
+    assert_eq!(game.side_to_move(), "none");
+    game.apply_move(String::from("+Q")).unwrap();
+    game.confirm_color('w').unwrap();
+    assert_eq!(game.phase(), "shop");
+    game.confirm_color('b').unwrap();
+    assert_eq!(game.phase(), "deploy");
+    assert_eq!(game.side_to_move(), "w");
+
+    for piece in ['K', 'k', 'Q'] {
+        assert_eq!(game.phase(), "deploy");
+        place_on_back_rank(&mut game, piece);
+    }
+    assert_eq!(game.phase(), "fight");
+    assert_ne!(game.side_to_move(), "none");
+}
+
+#[test]
+fn game_finishes_without_material() {
+    let mut game = ShuuroGame::new("standard");
+    game.confirm_color('w').unwrap();
+    game.confirm_color('b').unwrap();
+    place_on_back_rank(&mut game, 'K');
+    place_on_back_rank(&mut game, 'k');
+    assert_eq!(game.phase(), "finished");
+    assert_eq!(game.side_to_move(), "none");
+    assert_eq!(error_kind(&mut game, "a1_a2"), GameErrorKind::GameOver);
+    assert_eq!(error_kind(&mut game, "+Q"), GameErrorKind::GameOver);
+}
+
+#[test]
+fn moves_in_wrong_phase() {
+    let mut game = ShuuroGame::new("standard");
+    assert_eq!(error_kind(&mut game, "K@e1"), GameErrorKind::WrongPhase);
+    assert_eq!(error_kind(&mut game, "e2_e4"), GameErrorKind::WrongPhase);
+    assert_eq!(error_kind(&mut game, "+X"), GameErrorKind::IllegalMove);
+    game.apply_move(String::from("+Q")).unwrap();
+    game.confirm_color('w').unwrap();
+    assert_eq!(error_kind(&mut game, "+R"), GameErrorKind::AlreadyConfirmed);
+    let err = game.confirm_color('w').unwrap_err();
+    assert_eq!(err.kind, GameErrorKind::AlreadyConfirmed);
+    let err = game.confirm_color('x').unwrap_err();
+    assert_eq!(err.kind, GameErrorKind::IllegalMove);
+    game.confirm_color('b').unwrap();
+
+    assert_eq!(error_kind(&mut game, "+q"), GameErrorKind::WrongPhase);
+    assert_eq!(error_kind(&mut game, "e1_e2"), GameErrorKind::WrongPhase);
+    assert_eq!(error_kind(&mut game, "Q@a9"), GameErrorKind::IllegalMove);
+    let err = game.confirm_color('b').unwrap_err();
+    assert_eq!(err.kind, GameErrorKind::WrongPhase);
+    for piece in ['K', 'k', 'Q'] {
+        place_on_back_rank(&mut game, piece);
+    }
+
+    assert_eq!(error_kind(&mut game, "+q"), GameErrorKind::WrongPhase);
+    assert_eq!(error_kind(&mut game, "q@c8"), GameErrorKind::WrongPhase);
+    assert_eq!(error_kind(&mut game, "a8_a5"), GameErrorKind::IllegalMove);
+    assert_eq!(game.phase(), "fight");
+}
+
+#[test]
+fn confirmed_hand_gets_king() {
+    let mut game = ShuuroGame::new("standard");
+    game.apply_move(String::from("+Q")).unwrap();
+    game.apply_move(String::from("+r")).unwrap();
+    game.confirm_color('w').unwrap();
+    game.confirm_color('b').unwrap();
+    let sfen = game.generate_sfen();
+    let hand = sfen.split(' ').nth(2).unwrap();
+    for piece in ['K', 'Q', 'k', 'r'] {
+        assert_eq!(hand.matches(piece).count(), 1, "{}", sfen);
+    }
+}
+
+#[test]
+fn sfen_round_trip() {
+    for variant in VARIANTS {
+        let mut pos = position(variant);
+        pos.set_sfen(fight_sfen(variant)).unwrap();
+        let sfen = pos.generate_sfen();
+        let mut other = position(variant);
+        other.set_sfen(&sfen).unwrap();
+        assert_eq!(other.generate_sfen(), sfen);
+        assert_eq!(other.pieces_count(), pos.pieces_count());
+    }
+}
+
+#[test]
+fn invalid_sfen_keeps_position() {
+    let cases = [
+        ("8/8/8/8/8/8/8 w - 1", SfenErrorKind::RankCount),
+        ("8/8/8/8/8/8/8/7 w - 1", SfenErrorKind::RankLength),
+        ("8/8/8/8/8/8/8/7x w - 1", SfenErrorKind::UnknownPiece),
+        ("8/8/8/8/8/8/8/8 w KQX 1", SfenErrorKind::InvalidHand),
+        ("8/8/8/8/8/8/8/8 x - 1", SfenErrorKind::SideToMove),
+        ("8/8/8/8/8/8/8/8 w - a", SfenErrorKind::PlyCounter),
+        ("8/8/8/8/8/8/8/8 w", SfenErrorKind::MissingDataFields),
+    ];
+    let mut pos = position("standard");
+    pos.set_sfen(fight_sfen("standard")).unwrap();
+    let sfen = pos.generate_sfen();
+    for (case, kind) in cases {
+        assert_eq!(pos.set_sfen(case).unwrap_err().kind, kind, "{}", case);
+        assert_eq!(pos.generate_sfen(), sfen);
+    }
+    let mut pos = position("shuuro");
+    let err = pos.set_sfen(fight_sfen("standard")).unwrap_err();
+    assert_eq!(err.kind, SfenErrorKind::RankCount);
+}
+
+#[test]
+fn placement_hand() {
+    let mut pos = position("standard");
+    let sfen = "4K3/8/8/1L01L04/4L03/6L01/8/8 b RBNNNPPPPPPPPPPPPkqrbbnnp 1";
+    pos.set_sfen(sfen).unwrap();
+    assert_eq!(pos.count_hand_pieces(), "kqrbbnnpRBNNNPPPPPPPPPPPP");
+    assert_eq!(pos.map_plinths().len(), 4);
+    let moves = pos.place_moves('k');
+    let to = moves.get("K@").and_then(|m| m.first()).cloned().unwrap();
+    assert!(pos.place(format!("k@{}", to)).is_some());
+    assert_eq!(pos.count_hand_pieces(), "qrbbnnpRBNNNPPPPPPPPPPPP");
+    assert!(pos.undo());
+    assert_eq!(pos.generate_sfen(), sfen);
+    assert!(pos.redo());
+    assert!(!pos.can_redo());
+}
+
+#[test]
+fn undo_redo() {
+    let mut pos = position("standard");
+    let sfen = "4k3/8/8/8/8/8/8/4K3 w Nn 1";
+    pos.set_sfen(sfen).unwrap();
+    assert!(!pos.can_undo());
+    assert!(pos.place(String::from("N@d1")).is_some());
+    assert!(pos.place(String::from("n@d8")).is_some());
+    let fight = pos.generate_sfen();
+    for m in ["d1_c3", "d8_c6", "c3_d1", "c6_d8"] {
+        pos.make_move(String::from(m)).unwrap();
+    }
+    assert_eq!(pos.repetition().count, 2);
+    assert_eq!(pos.last_move(), "c6_d8");
+
+    assert!(pos.undo());
+    assert_eq!(pos.last_move(), "c3_d1");
+    assert_eq!(pos.repetition().count, 1);
+    assert!(pos.redo());
+    assert_eq!(pos.last_move(), "c6_d8");
+    assert_eq!(pos.repetition().count, 2);
+
+    for _ in 0..4 {
+        assert!(pos.undo());
+    }
+    assert_eq!(pos.generate_sfen(), fight);
+    assert_eq!(pos.repetition().count, 1);
+    assert!(pos.undo());
+    assert!(pos.undo());
+    assert!(!pos.can_undo());
+    assert_eq!(pos.generate_sfen(), sfen);
+    assert_eq!(pos.count_hand_pieces(), "nN");
+
+    assert!(pos.redo());
+    assert!(pos.redo());
+    assert!(pos.redo());
+    assert_eq!(pos.repetition().count, 1);
+    pos.make_move(String::from("d8_e6")).unwrap();
+    assert!(!pos.can_redo());
+    assert!(pos.undo());
+    assert_eq!(pos.last_move(), "d1_c3");
+}
+
+#[test]
+fn scripted_games() {
+    for variant in VARIANTS {
+        let mut pos = position(variant);
+        pos.set_sfen(fight_sfen(variant)).unwrap();
+        let start = pos.generate_sfen();
+        let mut played = 0;
+        for _ in 0..30 {
+            let Some(game_move) = first_move(&pos) else {
+                break;
+            };
+            let result = pos.make_move(game_move.clone()).unwrap();
+            assert_eq!(result.game_move, pos.last_move());
+            played += 1;
+            if result.outcome.game_over {
+                break;
+            }
+        }
+        assert!(played > 0, "{}", variant);
+        let end = pos.generate_sfen();
+        for _ in 0..played {
+            assert!(pos.undo());
+        }
+        assert!(!pos.can_undo());
+        assert_eq!(pos.generate_sfen(), start);
+        for _ in 0..played {
+            assert!(pos.redo());
+        }
+        assert_eq!(pos.generate_sfen(), end);
+    }
+}
+
+#[test]
+fn illegal_move_is_rejected() {
+    for variant in VARIANTS {
+        let mut pos = position(variant);
+        pos.set_sfen(fight_sfen(variant)).unwrap();
+        let sfen = pos.generate_sfen();
+        assert!(pos.make_move(String::from("a1_a6")).is_none());
+        assert_eq!(pos.generate_sfen(), sfen);
+        assert!(pos.legal_moves(1).is_empty());
+    }
+}
+
+#[test]
+fn outcomes() {
+    use shuuro_wasm::outcome::OutcomeKind;
+    let cases = [
+        ("k7/1Q6/2K5/8/8/8/8/8 b - 1", OutcomeKind::Checkmate),
+        ("k7/8/1Q6/8/8/8/8/2K5 b - 1", OutcomeKind::Stalemate),
+        ("k7/8/8/8/8/8/8/K7 w - 1", OutcomeKind::InsufficientMaterial),
+        ("k7/8/1Q6/8/8/8/8/K7 w - 1", OutcomeKind::Ongoing),
+    ];
+    for (sfen, kind) in cases {
+        let mut pos = position("standard");
+        pos.set_sfen(sfen).unwrap();
+        assert_eq!(pos.outcome().kind, kind, "{}", sfen);
+    }
+    let mut pos = position("standard");
+    pos.set_sfen(cases[0].0).unwrap();
+    assert_eq!(pos.outcome().winner.as_deref(), Some("white"));
+}
+
+#[test]
+fn best_move_finds_mate() {
+    use shuuro_wasm::search::{SearchOptions, MATE};
+    let mut pos = position("standard");
+    pos.set_sfen("k7/8/1K6/8/8/8/8/7R w - 1").unwrap();
+    let options = SearchOptions {
+        depth: 3,
+        ..SearchOptions::default()
+    };
+    let result = pos.best_move(options);
+    assert_eq!(result.best_move.as_deref(), Some("h1_h8"));
+    assert!(result.score >= MATE - 10);
+    let limited = pos.best_move(SearchOptions {
+        depth: 10,
+        nodes: 50,
+        time: 0.0,
+    });
+    assert!(limited.best_move.is_some());
+}
+
+#[test]
+fn best_move_takes_repetition_draw() {
+    use shuuro_wasm::search::SearchOptions;
+    let mut pos = position("standard");
+    pos.set_sfen("1n2k3/8/8/8/8/8/8/1N2K2R w - 1").unwrap();
+    for m in ["b1_c3", "b8_c6", "c3_b1"] {
+        pos.make_move(String::from(m)).unwrap();
+    }
+    let result = pos.best_move(SearchOptions {
+        depth: 3,
+        ..SearchOptions::default()
+    });
+    assert_eq!(result.best_move.as_deref(), Some("c6_b8"));
+    assert_eq!(result.score, 0);
+}
+
+#[test]
+fn deploy_move_is_legal() {
+    for level in 1..=3 {
+        let mut pos = position("standard");
+        pos.set_sfen("4K3/8/8/1L01L04/4L03/6L01/8/8 b RBNNNPPPPPPPPPPPPkqrbbnnp 1")
+            .unwrap();
+        let mut placed = 0;
+        let first = pos.deploy_move(level, 7);
+        assert_eq!(pos.deploy_move(level, 7), first);
+        while let Some(placement) = pos.deploy_move(level, placed) {
+            assert!(pos.place(placement.clone()).is_some(), "{}", placement);
+            placed += 1;
+        }
+        assert_eq!(placed, 25);
+        assert!(pos.count_hand_pieces().is_empty());
+    }
+}
+
+#[test]
+fn computer_army() {
+    use shuuro::shuuro12::square12::Square12;
+    use shuuro_wasm::buyer::{plan_army, ShopStyle};
+    let styles = [
+        ShopStyle::Balanced,
+        ShopStyle::Aggressive,
+        ShopStyle::FairyHeavy,
+    ];
+    assert_eq!("fairyHeavy".parse(), Ok(ShopStyle::FairyHeavy));
+    assert!("defensive".parse::<ShopStyle>().is_err());
+    assert!("".parse::<ShopStyle>().is_err());
+    for variant in VARIANTS {
+        for style in styles {
+            let mut shop = shuuro::Selection::<Square12>::default();
+            shop.update_variant(Variant::from(&variant.to_string()));
+            let army = plan_army(&shop, shuuro::Color::White, style, 7);
+            assert!(!army.is_empty());
+            assert_eq!(army, plan_army(&shop, shuuro::Color::White, style, 7));
+            let mut game = ShuuroGame::new(variant);
+            for m in army.iter() {
+                game.apply_move(m.clone()).unwrap();
+            }
+            if style == ShopStyle::FairyHeavy && variant.ends_with("Fairy") {
+                assert!(army
+                    .iter()
+                    .any(|m| ["+C", "+A", "+G"].contains(&m.as_str())));
+            }
+        }
+    }
+}
+
+#[test]
+fn evaluation() {
+    for variant in VARIANTS {
+        let mut pos = position(variant);
+        pos.set_sfen(fight_sfen(variant)).unwrap();
+        let eval = pos.evaluate();
+        assert_eq!(eval.material, 0, "{}", variant);
+        assert_eq!(eval.hand, 0);
+        assert_eq!(eval.mobility, 0, "{}", variant);
+        assert_eq!(
+            eval.score,
+            eval.material + eval.hand + eval.mobility + eval.king_safety + eval.plinths
+        );
+    }
+    let mut pos = position("standard");
+    pos.set_sfen("k7/8/1Q6/8/8/8/8/K7 w - 1").unwrap();
+    assert!(pos.evaluate().material > 0);
+    pos.set_sfen("4k3/8/8/8/8/8/8/4K3 w QR 1").unwrap();
+    assert!(pos.evaluate().hand > 0);
+}
+
+#[test]
+fn san_notation() {
+    let cases = [
+        ("standard", fight_sfen("standard"), "g1_f3", "Nf3"),
+        ("standard", "k7/8/8/3p4/4P3/8/8/K7 w - 1", "e4_d5", "exd5"),
+        ("standard", "k7/8/8/8/8/8/4K3/R6R w - 1", "a1_d1", "Rad1"),
+        ("standard", "k7/8/1K6/8/8/8/8/7R w - 1", "h1_h8", "Rh8#"),
+        (
+            "shuuro",
+            "k56/57/57/57/57/57/57/57/57/57/57/K55R w - 1",
+            "l1_l12",
+            "Rl12+",
+        ),
+    ];
+    for (variant, sfen, game_move, san) in cases {
+        let mut pos = position(variant);
+        pos.set_sfen(sfen).unwrap();
+        assert_eq!(pos.to_san(game_move, false).as_deref(), Some(san));
+    }
+    let mut pos = position("standard");
+    pos.set_sfen(fight_sfen("standard")).unwrap();
+    assert_eq!(pos.to_san("g1_f3", true).as_deref(), Some("♘f3"));
+    assert_eq!(pos.to_san("g1_g3", false), None);
+    assert_eq!(pos.to_san("N@e4", false).as_deref(), Some("N@e4"));
+    assert_eq!(pos.to_san("+n", false).as_deref(), Some("+N"));
+}
+
+#[test]
+fn parse_moves() {
+    use shuuro_wasm::notation::MoveParseErrorKind;
+    let mut pos = position("standard");
+    pos.set_sfen(fight_sfen("standard")).unwrap();
+    for input in ["Nf3", "g1f3", "g1-f3", "Ngf3", "Ng1f3"] {
+        assert_eq!(pos.parse_move(input).as_deref(), Ok("g1_f3"), "{}", input);
+    }
+    assert_eq!(pos.parse_move("e4").as_deref(), Ok("e2_e4"));
+    assert_eq!(
+        pos.parse_move("Nf4").unwrap_err().kind,
+        MoveParseErrorKind::Illegal
+    );
+    assert_eq!(
+        pos.parse_move("?!").unwrap_err().kind,
+        MoveParseErrorKind::Syntax
+    );
+    assert!(pos.complete_move("N").contains(&String::from("Nf3")));
+    assert!(pos.complete_move("g1").contains(&String::from("g1h3")));
+
+    pos.set_sfen("k7/8/8/8/8/8/4K3/R6R w - 1").unwrap();
+    let error = pos.parse_move("Rd1").unwrap_err();
+    assert_eq!(error.kind, MoveParseErrorKind::Ambiguous);
+    assert_eq!(error.candidates, vec!["a1_d1", "h1_d1"]);
+    assert_eq!(pos.parse_move("Rhd1").as_deref(), Ok("h1_d1"));
+
+    pos.set_sfen("k7/1p6/8/3p4/4P3/8/8/K7 w - 1").unwrap();
+    assert_eq!(pos.parse_move("exd5").as_deref(), Ok("e4_d5"));
+
+    let mut pos = position("shuuro");
+    pos.set_sfen("57/57/57/57/57/57/57/57/57/57/57/57 w Kk 1")
+        .unwrap();
+    let square = pos.place_moves('K')["K@"][0].clone();
+    let placement = format!("K@{}", square);
+    assert_eq!(pos.parse_move(&placement), Ok(placement.clone()));
+    assert_eq!(
+        pos.parse_move(&format!("Q@{}", square)).unwrap_err().kind,
+        MoveParseErrorKind::Illegal
+    );
+}
+
+#[test]
+fn game_record() {
+    use shuuro_wasm::game::Phase;
+    use shuuro_wasm::record::{RecordErrorKind, ShuuroRecord};
+    let mut game = ShuuroGame::new("standard");
+    let mut record = ShuuroRecord::new("standard");
+    record.set_tag("White", "Anna \"A\"");
+    record.set_tag("TimeControl", "300+5");
+    for m in ["+Q", "+r", "+P"] {
+        game.apply_move(String::from(m)).unwrap();
+        record.push(String::from(m), None);
+    }
+    game.confirm_color('w').unwrap();
+    game.confirm_color('b').unwrap();
+    while game.current_phase() == Phase::Deploy {
+        let m = game.position().deploy_move(2, 0).unwrap();
+        game.apply_move(m.clone()).unwrap();
+        record.push(m, None);
+    }
+    for ply in 0..4 {
+        let m = first_move(game.position()).unwrap();
+        game.apply_move(m.clone()).unwrap();
+        let comment = if ply == 1 {
+            Some(String::from("reply"))
+        } else {
+            None
+        };
+        record.push(m, comment);
+    }
+    let text = record.write_text().unwrap();
+    assert!(text.contains("Shop:\n+Q +r +P"), "{}", text);
+    assert!(text.contains("{reply}"), "{}", text);
+    let read = ShuuroRecord::read_text(&text).unwrap();
+    assert_eq!(read, record);
+    assert_eq!(read.tag("White").as_deref(), Some("Anna \"A\""));
+    assert_eq!(read.replay().unwrap().generate_sfen(), game.generate_sfen());
+
+    let errors = [
+        ("Fight:\n1. e4 {open", RecordErrorKind::Comment),
+        ("1. e4", RecordErrorKind::Section),
+        ("Deploy:\n+Q", RecordErrorKind::Section),
+        (
+            "Shop:\n+Q\n\nDeploy:\n1. Q@a9",
+            RecordErrorKind::IllegalMove,
+        ),
+        ("[Variant standard]", RecordErrorKind::Tag),
+        ("[Result \"1-0\"]\nShop:\n+Q 0-1", RecordErrorKind::Result),
+    ];
+    for (text, kind) in errors {
+        let error = ShuuroRecord::read_text(text).unwrap_err();
+        assert_eq!(error.kind, kind, "{}", text);
+    }
+}
+
+#[test]
+fn replay_navigation() {
+    use shuuro_wasm::game::Phase;
+    use shuuro_wasm::replay::ShuuroReplay;
+    let purchases = ["+Q", "+R", "+N", "+P", "+P", "+q", "+b", "+n", "+p", "+p"];
+    let (game, moves) = play_game("shuuro", &purchases, 20);
+    let mut sfens = vec![];
+    let mut replay = ShuuroReplay::new("shuuro");
+    replay.load_moves(moves.clone()).unwrap();
+    for ply in 0..=moves.len() {
+        sfens.push(replay.position(ply).unwrap().sfen);
+    }
+    assert_eq!(replay.ply(), 0);
+    assert_eq!(sfens.last(), Some(&game.generate_sfen()));
+    assert!(replay.position(moves.len() + 1).is_none());
+
+    let last = replay.position(moves.len()).unwrap();
+    assert_eq!(last.phase, Phase::Fight);
+    assert_eq!(last.last_move.as_ref(), moves.last());
+    assert_eq!(last.pieces, game.position().map_pieces());
+
+    assert!(replay.goto(moves.len()));
+    assert!(!replay.next_ply());
+    assert!(replay.prev_ply());
+    assert_eq!(replay.game().generate_sfen(), sfens[moves.len() - 1]);
+    for ply in [3, 40, 17, 16, 0, moves.len()] {
+        assert!(replay.goto(ply));
+        assert_eq!(replay.game().generate_sfen(), sfens[ply], "{}", ply);
+    }
+    assert!(!replay.goto(moves.len() + 1));
+    while replay.prev_ply() {}
+    assert_eq!(replay.ply(), 0);
+    assert_eq!(replay.game().current_phase(), Phase::Shop);
+
+    let mut illegal = moves.clone();
+    illegal.push(String::from("a1_a1"));
+    assert!(ShuuroReplay::new("shuuro").load_moves(illegal).is_err());
+}
+
+#[test]
+fn replay_keeps_positions_only() {
+    use shuuro_wasm::replay::ShuuroReplay;
+    let (_, moves) = play_game("standard", &["+Q", "+r", "+P"], 10);
+    let mut replay = ShuuroReplay::new("standard");
+    replay.load_moves(moves.clone()).unwrap();
+    let sfens: Vec<String> = (0..=moves.len())
+        .map(|ply| replay.position(ply).unwrap().sfen)
+        .collect();
+    for ply in [1, 5, 6, moves.len() - 1, moves.len(), 2] {
+        assert!(replay.goto(ply));
+        assert!(!replay.game().position().can_undo(), "{}", ply);
+        let current = replay.position(ply).unwrap();
+        assert_eq!(current.sfen, sfens[ply]);
+        assert_eq!(current.sfen, replay.game().generate_sfen());
+    }
+}
+
+#[test]
+fn analysis_tree() {
+    use shuuro_wasm::analysis::ShuuroAnalysis;
+    use shuuro_wasm::position::ShuuroPosition;
+    use shuuro_wasm::record::ShuuroRecord;
+    let sfen = fight_sfen("standard");
+    let mut board = ShuuroPosition::new("standard");
+    board.container_mut().set_sfen(sfen).unwrap();
+    let mut tree = ShuuroAnalysis::new("standard", sfen);
+    let e4 = tree.play_move(board.container_mut(), "e4").unwrap();
+    let e5 = tree.play_move(board.container_mut(), "e5").unwrap();
+    assert!(tree.back(&mut board));
+    let c5 = tree.play_move(board.container_mut(), "c7_c5").unwrap();
+    assert_eq!(tree.node(e4).unwrap().children, vec![e5, c5]);
+    assert!(tree.back(&mut board));
+    assert_eq!(tree.play_move(board.container_mut(), "e7e5"), Ok(e5));
+    assert!(tree.play_move(board.container_mut(), "Ke3").is_err());
+    assert!(tree.set_comment(c5, Some(String::from("sicilian"))));
+
+    let text = tree.write_text();
+    assert!(text.contains("1. e4 e5 (1... c5 {sicilian})"), "{}", text);
+    let record = ShuuroRecord::read_text(&text).unwrap();
+    let moves: Vec<String> = record
+        .record_moves()
+        .iter()
+        .map(|m| m.game_move.clone())
+        .collect();
+    assert_eq!(moves, tree.mainline_moves());
+    assert_eq!(moves, vec!["e2_e4", "e7_e5"]);
+
+    assert!(tree.promote(c5));
+    assert_eq!(tree.mainline_moves(), vec!["e2_e4", "c7_c5"]);
+    assert!(tree.write_text().contains("1. e4 c5 {sicilian} (1... e5)"));
+
+    let mut expected = position("standard");
+    expected.set_sfen(sfen).unwrap();
+    expected.make_move(String::from("e2_e4")).unwrap();
+    let after_e4 = expected.generate_sfen();
+    expected.make_move(String::from("c7_c5")).unwrap();
+    assert!(tree.goto(&mut board, c5));
+    assert_eq!(board.container().generate_sfen(), expected.generate_sfen());
+
+    assert!(tree.delete(&mut board, c5));
+    assert_eq!(tree.current(), e4);
+    assert!(tree.node(c5).is_none());
+    assert_eq!(board.container().generate_sfen(), after_e4);
+    assert_eq!(tree.mainline_moves(), vec!["e2_e4", "e7_e5"]);
+    assert!(!tree.delete(&mut board, 0));
+}
+
+#[test]
+fn analysis_record_replay() {
+    use shuuro_wasm::analysis::ShuuroAnalysis;
+    use shuuro_wasm::position::ShuuroPosition;
+    use shuuro_wasm::record::ShuuroRecord;
+    use shuuro_wasm::replay::ShuuroReplay;
+    let sfen = "4k3/8/3L04/8/8/5L02/8/R3K2N w - 1";
+    let mut board = ShuuroPosition::new("standard");
+    board.container_mut().set_sfen(sfen).unwrap();
+    let mut tree = ShuuroAnalysis::new("standard", sfen);
+    for m in ["Ra7", "Kf8", "Ng3"] {
+        tree.play_move(board.container_mut(), m).unwrap();
+    }
+    let record = ShuuroRecord::read_text(&tree.write()).unwrap();
+    assert_eq!(record.tag("Sfen").as_deref(), Some(sfen));
+    let mut replay = ShuuroReplay::with_record(&record).unwrap();
+    assert_eq!(replay.plies(), 3);
+    assert_eq!(replay.position(0).unwrap().sfen, sfen);
+    assert_eq!(replay.position(0).unwrap().plinths.len(), 2);
+    assert!(replay.goto(3));
+    assert_eq!(
+        replay.game().generate_sfen(),
+        board.container().generate_sfen()
+    );
+    assert_eq!(
+        replay.position(3).unwrap().last_move.as_deref(),
+        Some("h1_g3")
+    );
+}
+
+#[test]
+fn attack_maps() {
+    let mut pos = position("standard");
+    pos.set_sfen("k7/8/8/8/8/8/8/R3K3 w - 1").unwrap();
+    let white = pos.attacked_squares(0);
+    for sq in ["a2", "a8", "d1", "e2", "f1", "b1"] {
+        assert!(white.contains(&String::from(sq)), "{}", sq);
+    }
+    assert!(white.contains(&String::from("e1")));
+    assert!(!white.contains(&String::from("b2")));
+    assert_eq!(pos.attackers_of("d1"), vec!["a1", "e1"]);
+    assert_eq!(pos.attackers_of("a8"), vec!["a1"]);
+    assert_eq!(pos.attackers_of("b7"), vec!["a8"]);
+
+    pos.set_sfen("k7/8/8/8/L07/8/1N6/4K3 w - 1").unwrap();
+    let white = pos.attacked_squares(0);
+    assert!(white.contains(&String::from("a4")));
+    assert_eq!(pos.attackers_of("a4"), vec!["b2"]);
+
+    pos.set_sfen("k7/8/8/8/L07/8/8/R3K3 w - 1").unwrap();
+    let white = pos.attacked_squares(0);
+    assert!(white.contains(&String::from("a3")));
+    assert!(!white.contains(&String::from("a4")));
+    assert!(!white.contains(&String::from("a5")));
+}
+
+#[test]
+fn pins_and_checks() {
+    let mut pos = position("standard");
+    pos.set_sfen("4k3/4r3/8/8/8/8/4B3/4K3 w - 1").unwrap();
+    let threats = pos.threats();
+    assert!(threats.checkers.is_empty());
+    assert_eq!(threats.pins.len(), 1);
+    assert_eq!(threats.pins[0].square, "e2");
+    assert_eq!(threats.pins[0].pinner, "e7");
+    assert_eq!(
+        threats.pins[0].ray,
+        vec!["e2", "e3", "e4", "e5", "e6", "e7"]
+    );
+
+    pos.set_sfen("4k3/8/8/8/8/8/8/r3K3 w - 1").unwrap();
+    let threats = pos.threats();
+    assert_eq!(threats.checkers, vec!["a1"]);
+    assert_eq!(threats.check_rays, vec![vec!["d1", "c1", "b1", "a1"]]);
+    assert_eq!(threats.blocks, vec!["d1", "c1", "b1"]);
+
+    pos.set_sfen("4k3/8/8/8/8/3n4/8/r3K3 w - 1").unwrap();
+    let threats = pos.threats();
+    assert_eq!(threats.checkers.len(), 2);
+    assert!(threats.blocks.is_empty());
+
+    pos.set_sfen("4k3/8/8/8/8/8/4N3/4R1K1 w - 1").unwrap();
+    let threats = pos.threats();
+    assert_eq!(threats.discovered.len(), 1);
+    assert_eq!(threats.discovered[0].square, "e2");
+    assert_eq!(threats.discovered[0].pinner, "e1");
+
+    pos.set_sfen("4k3/4r3/8/4L03/8/8/4B3/4K3 w - 1").unwrap();
+    assert!(pos.threats().pins.is_empty());
+}
+
+#[test]
+fn legal_moves_from_square() {
+    let positions = VARIANTS.iter().map(|v| (*v, fight_sfen(v))).chain([
+        ("standard", "4k3/4r3/8/8/8/8/4B3/4K3 w - 1"),
+        ("standard", "4k3/8/8/8/4r3/8/3N4/4K3 w - 1"),
+        ("standard", "4k3/8/8/b7/4r3/8/3N4/4K3 w - 1"),
+        ("standard", "4k3/8/2L05/8/4r3/8/3PB3/3QK2N w - 1"),
+    ]);
+    for (variant, sfen) in positions {
+        let mut pos = position(variant);
+        pos.set_sfen(sfen).unwrap();
+        for (from, to) in pos.legal_moves(0) {
+            let mut expected = to.clone();
+            expected.sort();
+            let mut found = pos.legal_moves_from(&from);
+            found.sort();
+            assert_eq!(found, expected, "{} {}", variant, from);
+            for to in to {
+                assert!(pos.is_legal(&format!("{}_{}", from, to)));
+            }
+        }
+    }
+
+    let mut pos = position("standard");
+    pos.set_sfen("4k3/4r3/8/8/8/8/4B3/4K3 w - 1").unwrap();
+    assert!(pos.legal_moves_from("e2").is_empty());
+    pos.set_sfen("4k3/8/8/8/4r3/8/3N4/4K3 w - 1").unwrap();
+    assert_eq!(pos.legal_moves_from("d2"), vec!["e4"]);
+    let mut king = pos.legal_moves_from("e1");
+    king.sort();
+    assert_eq!(king, vec!["d1", "f1", "f2"]);
+
+    let mut pos = position("standard");
+    pos.set_sfen("k7/8/8/8/8/8/8/R3K3 w - 1").unwrap();
+    assert!(pos.legal_moves_from("a8").is_empty());
+    assert!(pos.legal_moves_from("c3").is_empty());
+    assert!(pos.legal_moves_from("z9").is_empty());
+    assert!(pos.is_legal("a1_a7"));
+    assert!(!pos.is_legal("a1_b2"));
+    assert!(!pos.is_legal("a8_a7"));
+    assert!(!pos.is_legal("a1"));
+
+    let mut pos = position("shuuro");
+    pos.set_sfen("57/57/57/57/57/57/57/57/57/57/57/57 w Kk 1")
+        .unwrap();
+    let square = pos.place_moves('K')["K@"][0].clone();
+    assert!(pos.is_legal(&format!("K@{}", square)));
+    assert!(!pos.is_legal(&format!("Q@{}", square)));
+}
+
+#[test]
+fn legal_moves_from_every_square() {
+    let positions = [
+        ("standard", "rn2k3/8/8/L07/7L0/2L0L01L02/8/1NB1K1NR w - 1"),
+        ("standard", "rn2k3/8/8/L07/7L0/2L0L01L02/8/1NB1K1NR b - 1"),
+        ("standard", "4r2k/1p3n2/8/8/L07/4NL02/8/R3K1N1 w - 1"),
+        ("standardFairy", "r3k3/8/2L05/8/3C4/8/1A2N3/4K3 w - 1"),
+        (
+            "shuuroFairy",
+            "55k1/57/57/57/3L05L02/57/57/2G9/57/57/57/Q55K w - 1",
+        ),
+    ];
+    for (variant, sfen) in positions {
+        let mut pos = position(variant);
+        pos.set_sfen(sfen).unwrap();
+        let color = if pos.side_to_move() == "w" { 0 } else { 1 };
+        let moves = pos.legal_moves(color);
+        let size = if variant.starts_with("standard") {
+            8
+        } else {
+            12
+        };
+        for file in ('a'..='l').take(size) {
+            for rank in 1..=size {
+                let square = format!("{}{}", file, rank);
+                let mut expected = moves.get(&square).cloned().unwrap_or_default();
+                expected.sort();
+                let mut found = pos.legal_moves_from(&square);
+                found.sort();
+                assert_eq!(found, expected, "{} {}", sfen, square);
+            }
+        }
+    }
+}
+
+#[test]
+fn premove_queue() {
+    let mut pos = position("standard");
+    pos.set_sfen("k7/1r6/8/8/8/8/8/R3K3 b - 1").unwrap();
+    assert!(pos.legal_moves(0).is_empty());
+    let premoves = pos.premove_moves();
+    assert!(premoves["a1"].contains(&String::from("a8")));
+    assert!(!premoves["a1"].contains(&String::from("e1")));
+    assert!(!premoves.contains_key("b7"));
+    assert!(!pos.premove("a1_b2"));
+    assert!(pos.premove("a1_a6"));
+    assert!(pos.premove("a6_b6"));
+    assert_eq!(pos.premove_queue(), vec!["a1_a6", "a6_b6"]);
+
+    let result = pos.make_move(String::from("b7_b8")).unwrap();
+    let premove = result.premove.unwrap();
+    assert!(premove.played);
+    assert_eq!(pos.side_to_move(), "b");
+    assert_eq!(pos.premove_queue(), vec!["a6_b6"]);
+
+    // check from b1 makes queued premove illegal
+    let result = pos.make_move(String::from("b8_b1")).unwrap();
+    let premove = result.premove.unwrap();
+    assert!(!premove.played);
+    assert_eq!(premove.game_move, "a6_b6");
+    assert!(pos.premove_queue().is_empty());
+    assert_eq!(pos.side_to_move(), "w");
+
+    let result = pos.make_move(String::from("e1_e2")).unwrap();
+    assert!(result.premove.is_none());
+
+    pos.set_sfen("k7/1r6/8/8/8/8/8/R3K3 b - 1").unwrap();
+    assert!(pos.premove("a1_a6"));
+    pos.cancel_premoves();
+    assert!(pos.premove_queue().is_empty());
+}
+
+#[test]
+fn promotion_choices() {
+    let mut pos = position("standard");
+    pos.set_sfen("k7/4P3/8/8/8/8/8/4K3 w - 1").unwrap();
+    let destinations = pos.legal_destinations(0);
+    let e8 = destinations["e7"]
+        .iter()
+        .find(|d| d.square == "e8")
+        .unwrap();
+    assert_eq!(e8.promotion, vec!["Q", "R", "B", "N"]);
+    assert!(destinations["e1"].iter().all(|d| d.promotion.is_empty()));
+    assert!(pos.promotions("e1_e2").is_empty());
+    assert!(pos.is_legal("e7_e8=N"));
+    assert!(!pos.is_legal("e7_e8=K"));
+    assert!(pos.make_move(String::from("e7_e8=K")).is_none());
+
+    pos.make_move(String::from("e1_d1")).unwrap();
+    pos.make_move(String::from("a8_b8")).unwrap();
+    let result = pos.make_move(String::from("e7_e8=N")).unwrap();
+    assert_eq!(result.game_move, "e7_e8=N");
+    assert_eq!(pos.map_pieces()["e8"].role, "n-piece");
+    assert_eq!(pos.last_move(), "e7_e8=N");
+    assert_eq!(pos.sfen_history().len(), 3);
+    assert_eq!(pos.sfen_history().last(), Some(&pos.generate_sfen()));
+    pos.make_move(String::from("b8_a8")).unwrap();
+    assert_eq!(pos.last_move(), "b8_a8");
+    assert_eq!(pos.sfen_history().len(), 4);
+    assert!(pos.undo());
+    assert_eq!(pos.last_move(), "e7_e8=N");
+    assert_eq!(pos.sfen_history().len(), 3);
+    assert!(pos.undo());
+    assert!(pos.undo());
+    assert!(pos.undo());
+    assert_eq!(pos.parse_move("e8=R").as_deref(), Ok("e7_e8=R"));
+    assert_eq!(pos.to_san("e7_e8=R", false).as_deref(), Some("e8=R+"));
+
+    let mut pos = position("standardFairy");
+    pos.set_sfen("k7/8/8/8/8/8/4p3/K7 b - 1").unwrap();
+    let promotion = pos.promotions("e2_e1");
+    assert_eq!(promotion, vec!["q", "r", "b", "n", "c", "a", "g"]);
+    let result = pos.make_move(String::from("e2_e1=C")).unwrap();
+    assert_eq!(result.game_move, "e2_e1=C");
+}
+
+fn phase_control(
+    kind: shuuro_wasm::clock::ClockKind,
+    base: u64,
+    increment: u64,
+) -> shuuro_wasm::clock::PhaseControl {
+    shuuro_wasm::clock::PhaseControl {
+        kind,
+        base,
+        increment,
+    }
+}
+
+#[test]
+fn clock_kinds() {
+    use shuuro::Color;
+    use shuuro_wasm::clock::{ClockKind, ShuuroClock, TimeControl};
+    use shuuro_wasm::game::Phase;
+
+    let cases = [
+        (ClockKind::SuddenDeath, 1500, 8500),
+        (ClockKind::SuddenDeath, 5000, 5000),
+        (ClockKind::Fischer, 1500, 10500),
+        (ClockKind::Bronstein, 1500, 10000),
+        (ClockKind::Bronstein, 5000, 7000),
+        (ClockKind::Delay, 1500, 10000),
+        (ClockKind::Delay, 5000, 7000),
+    ];
+    for (kind, elapsed, remaining) in cases {
+        let fight = phase_control(kind, 10000, 2000);
+        let control = TimeControl {
+            shop: fight,
+            deploy: fight,
+            fight,
+        };
+        let mut clock = ShuuroClock::new(control);
+        clock.start_phase(Phase::Fight, Color::White, 1000);
+        assert!(clock.is_running(Color::White));
+        assert!(!clock.is_running(Color::Black));
+        assert!(clock.press(Color::White, 1000 + elapsed));
+        assert_eq!(clock.white.remaining, remaining, "{:?} {}", kind, elapsed);
+    }
+
+    let control = TimeControl {
+        shop: phase_control(ClockKind::SuddenDeath, 1000, 0),
+        deploy: phase_control(ClockKind::SuddenDeath, 1000, 0),
+        fight: phase_control(ClockKind::Delay, 1000, 500),
+    };
+    let mut clock = ShuuroClock::new(control);
+    clock.start_phase(Phase::Fight, Color::Black, 0);
+    assert_eq!(clock.remaining(Color::Black, 400), 1000);
+    assert_eq!(clock.remaining(Color::Black, 900), 600);
+    assert_eq!(clock.check_flag(1400), None);
+    assert_eq!(clock.check_flag(1500).as_deref(), Some("black"));
+    assert!(!clock.press(Color::Black, 1600));
+
+    let mut clock = ShuuroClock::new(control);
+    clock.start_phase(Phase::Shop, Color::White, 0);
+    assert!(clock.press(Color::Black, 300));
+    assert!(!clock.press(Color::White, 1200));
+    assert_eq!(clock.flagged.as_deref(), Some("white"));
+    assert_eq!(clock.phase, Phase::Finished);
+    assert!(!clock.is_running(Color::White));
+    assert_eq!(clock.remaining(Color::Black, 5000), 700);
+}
+
+#[test]
+fn game_clock() {
+    use shuuro_wasm::clock::{ClockKind, TimeControl};
+    use shuuro_wasm::game::{GameErrorKind, Phase};
+
+    let control = TimeControl {
+        shop: phase_control(ClockKind::SuddenDeath, 30000, 0),
+        deploy: phase_control(ClockKind::SuddenDeath, 20000, 0),
+        fight: phase_control(ClockKind::Fischer, 60000, 1000),
+    };
+    let mut game = ShuuroGame::new("standard");
+    game.set_clock(control, 0);
+    game.apply_move_at(String::from("+Q"), 1000).unwrap();
+    game.confirm_color_at('w', 5000).unwrap();
+    let clock = game.clock().unwrap().view(6000);
+    assert_eq!(clock.white, 25000);
+    assert_eq!(clock.black, 24000);
+    assert_eq!(clock.running, vec!["black"]);
+    game.confirm_color_at('b', 10000).unwrap();
+    assert_eq!(game.current_phase(), Phase::Deploy);
+    let clock = game.clock().unwrap().view(10000);
+    assert_eq!((clock.white, clock.black), (20000, 20000));
+    assert_eq!(clock.phase, Phase::Deploy);
+
+    let mut now = 10000;
+    while game.current_phase() == Phase::Deploy {
+        now += 100;
+        let m = game.position().deploy_move(1, 0).unwrap();
+        game.apply_move_at(m, now).unwrap();
+    }
+    let clock = game.clock().unwrap().view(now);
+    assert_eq!((clock.white, clock.black), (60000, 60000));
+    assert_eq!(clock.phase, Phase::Fight);
+
+    let white_moves = game.side_to_move() == "w";
+    let m = first_move(game.position()).unwrap();
+    game.apply_move_at(m, now + 5000).unwrap();
+    let clock = game.clock().unwrap().view(now + 8000);
+    let (mover, waiting) = if white_moves {
+        (clock.white, clock.black)
+    } else {
+        (clock.black, clock.white)
+    };
+    assert_eq!(mover, 56000);
+    assert_eq!(waiting, 57000);
+
+    let saved = game.clock().unwrap().clone();
+    assert_eq!(game.timeout_at(now + 64999), None);
+    let m = first_move(game.position()).unwrap();
+    let error = game.apply_move_at(m, now + 65000).unwrap_err();
+    assert_eq!(error.kind, GameErrorKind::Timeout);
+    assert_eq!(game.current_phase(), Phase::Finished);
+    let flagged = if white_moves { "black" } else { "white" };
+    assert_eq!(game.clock().unwrap().flagged.as_deref(), Some(flagged));
+
+    let mut restored = ShuuroGame::new("standard");
+    restored.restore_clock(saved.clone());
+    assert_eq!(restored.clock(), Some(&saved));
+}
+
+#[test]
+fn zobrist_hash() {
+    for variant in VARIANTS {
+        let mut pos = position(variant);
+        pos.set_sfen(fight_sfen(variant)).unwrap();
+        let start = pos.zobrist();
+        let mut seen = vec![start];
+        for _ in 0..6 {
+            let m = first_move(&pos).unwrap();
+            pos.make_move(m).unwrap();
+            let mut fresh = position(variant);
+            fresh.set_sfen(&pos.generate_sfen()).unwrap();
+            assert_eq!(pos.zobrist(), fresh.zobrist(), "{}", variant);
+            seen.push(pos.zobrist());
+        }
+        assert_ne!(seen[0], seen[1], "{}", variant);
+        while pos.undo() {}
+        assert_eq!(pos.zobrist(), start);
+    }
+
+    let mut pos = position("standard");
+    pos.set_sfen("4k3/8/8/8/8/8/8/4K3 w - 1").unwrap();
+    let white = pos.zobrist();
+    pos.set_sfen("4k3/8/8/8/8/8/8/4K3 b - 1").unwrap();
+    assert_ne!(pos.zobrist(), white);
+    pos.set_sfen("4k3/8/8/8/4L03/8/8/4K3 w - 1").unwrap();
+    assert_ne!(pos.zobrist(), white);
+
+    let mut pos = position("shuuro");
+    pos.set_sfen("57/57/57/57/57/57/57/57/57/57/57/57 w KQkq 1")
+        .unwrap();
+    let square = pos.place_moves('K')["K@"][0].clone();
+    pos.place(format!("K@{}", square)).unwrap();
+    let mut fresh = position("shuuro");
+    fresh.set_sfen(&pos.generate_sfen()).unwrap();
+    assert_eq!(pos.zobrist(), fresh.zobrist());
+}
+
+#[test]
+fn repetition_rules() {
+    use shuuro_wasm::outcome::{OutcomeKind, RepetitionRule};
+
+    let shuffle = ["b1_c3", "b8_c6", "c3_b1", "c6_b8"];
+    let mut pos = position("standard");
+    pos.set_sfen("rn2k3/8/8/8/8/8/8/RN2K3 w - 1").unwrap();
+    assert_eq!(pos.repetition().count, 1);
+    for m in shuffle.iter().chain(shuffle.iter()) {
+        pos.make_move(String::from(*m)).unwrap();
+    }
+    let repetition = pos.repetition();
+    assert_eq!(repetition.count, 3);
+    assert!(repetition.claimable);
+    assert!(!repetition.automatic);
+    assert!(!pos.outcome().game_over);
+    for m in shuffle.iter().chain(shuffle.iter()) {
+        pos.make_move(String::from(*m)).unwrap();
+    }
+    assert_eq!(pos.repetition().count, 5);
+    assert_eq!(pos.outcome().kind, OutcomeKind::Repetition);
+    assert!(pos.undo());
+    assert_eq!(pos.repetition().count, 4);
+
+    let mut pos = position("shuuro");
+    pos.set_sfen(fight_sfen("shuuro")).unwrap();
+    let shuffle = ["d2_c4", "d11_c9", "c4_d2", "c9_d11"];
+    for m in shuffle.iter().chain(shuffle.iter()) {
+        pos.make_move(String::from(*m)).unwrap();
+    }
+    assert!(pos.repetition().automatic);
+    assert_eq!(pos.outcome().kind, OutcomeKind::Repetition);
+
+    pos.set_repetition_rule(RepetitionRule {
+        claim: 0,
+        automatic: 0,
+    });
+    let repetition = pos.repetition();
+    assert_eq!(repetition.count, 3);
+    assert!(!repetition.claimable && !repetition.automatic);
+    assert!(!pos.outcome().game_over);
+}
@@ -0,0 +1,75 @@
+//! Perft reference counts for every board size.
+
+use shuuro::Variant;
+use shuuro_wasm::PositionContainer;
+
+const START_6: &str = "rnbkqn/pppppp/6/6/PPPPPP/RNBKQN w - 1";
+const START_8: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - 1";
+const START_12: &str = "57/2rnbqkbnr2/2pppppppp2/57/57/57/57/57/57/2PPPPPPPP2/2RNBQKBNR2/57 w - 1";
+const PLINTHS_8: &str = "rn2k3/8/8/L07/7L0/2L0L01L02/8/1NB1K1NR w - 1";
+const PIN_8: &str = "4r2k/1p3n2/8/8/L07/4NL02/8/R3K1N1 w - 1";
+
+/// (variant, sfen, depth, nodes)
+const PERFT: [(&str, &str, u8, u64); 25] = [
+    // 8x8
+    ("standard", "7k/8/8/8/8/8/8/K7 w - 1", 1, 3),
+    ("standard", "7k/8/8/8/8/8/8/K7 w - 1", 2, 9),
+    ("standard", "7k/8/8/8/8/8/8/R3K3 w - 1", 1, 15),
+    // rook is blocked by plinth on a4
+    ("standard", "7k/8/8/8/L07/8/8/R3K3 w - 1", 1, 10),
+    // bishop is blocked by plinth on e3
+    ("standard", "7k/8/8/8/8/4L03/8/2B1K3 w - 1", 1, 8),
+    // knight can jump on plinth on f3
+    ("standard", "7k/8/8/8/8/5L02/8/4K1N1 w - 1", 1, 8),
+    // start position, pawns move one square
+    ("standard", START_8, 1, 12),
+    ("standard", START_8, 2, 144),
+    ("standard", START_8, 3, 2124),
+    // promotion with and without capture, pawn becomes queen
+    ("standard", "r3k3/1P6/8/8/8/8/8/4K3 w - 1", 2, 82),
+    ("standard", "r3k3/1P6/8/8/8/8/8/4K3 w - 1", 3, 719),
+    // plinths block bishop, rook and king, knights jump on c3 and f3
+    ("standard", PLINTHS_8, 1, 20),
+    ("standard", PLINTHS_8, 2, 195),
+    ("standard", PLINTHS_8, 3, 4020),
+    // knight on e3 is pinned, rook is stopped by plinth on a4, knight jumps on plinth on f3
+    ("standard", PIN_8, 1, 13),
+    ("standard", PIN_8, 2, 260),
+    ("standard", PIN_8, 3, 4996),
+    // fairy pieces
+    ("standardFairy", "7k/8/8/8/8/8/8/C3K3 w - 1", 1, 17),
+    ("standardFairy", "8/7k/8/8/8/8/8/A3K3 w - 1", 1, 14),
+    // 12x12
+    (
+        "shuuro",
+        "55k1/57/57/57/57/57/57/57/57/57/57/Q55K w - 1",
+        1,
+        35,
+    ),
+    ("shuuro", START_12, 2, 1156),
+    ("shuuro", START_12, 3, 42764),
+    // 6x6
+    ("shuuroMini", "5k/6/6/6/6/R4K w - 1", 1, 12),
+    ("shuuroMini", START_6, 2, 81),
+    ("shuuroMini", START_6, 3, 921),
+];
+
+#[test]
+fn perft_reference() {
+    for (variant, sfen, depth, nodes) in PERFT {
+        let mut pos = PositionContainer::new(Variant::from(&variant.to_string()));
+        pos.set_sfen(sfen).unwrap();
+        assert_eq!(pos.perft(depth), nodes, "{} depth {}", sfen, depth);
+    }
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    for (variant, sfen, depth, nodes) in PERFT {
+        let mut pos = PositionContainer::new(Variant::from(&variant.to_string()));
+        pos.set_sfen(sfen).unwrap();
+        let divide = pos.divide(depth);
+        assert_eq!(divide.len() as u64, pos.perft(1));
+        assert_eq!(divide.iter().map(|d| d.1).sum::<u64>(), nodes);
+    }
+}
@@ -3,8 +3,11 @@
 #![cfg(target_arch = "wasm32")]
 
 extern crate wasm_bindgen_test;
+use js_sys::Array;
+use shuuro::Variant;
 use shuuro_wasm::position::ShuuroPosition;
 use shuuro_wasm::selection::ShuuroShop;
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_test::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
@@ -12,7 +15,7 @@ wasm_bindgen_test_configure!(run_in_browser);
 #[wasm_bindgen_test]
 fn fairy_shop() {
     let mut shop = ShuuroShop::new();
-    shop.change_variant(String::from("shuuroFairy"));
+    shop.change_variant(Variant::ShuuroFairy as u8);
     shop.buy(String::from("+G"));
     shop.buy(String::from("+G"));
     shop.buy(String::from("+G"));
@@ -23,7 +26,7 @@ fn fairy_shop() {
 fn standard_placement() {
     let mut pos = ShuuroPosition::new("standard");
     let sfen = "8/8/3L04/1L06/8/L01L05/8/8 w kqnKQR 0";
-    pos.set_sfen(sfen);
+    pos.set_sfen(sfen).unwrap();
     let m = pos.place_moves('K');
 }
 
@@ -43,8 +46,87 @@ fn placement_hand() {
     ];
     for case in cases {
         let mut pos = ShuuroPosition::new(case.0);
-        pos.set_sfen(case.1);
+        pos.set_sfen(case.1).unwrap();
         let hand = pos.count_hand_pieces();
         assert_eq!(hand, String::from(case.2));
     }
 }
+
+#[wasm_bindgen_test]
+fn invalid_sfen_rollback() {
+    let mut pos = ShuuroPosition::new("standard");
+    let sfen = "8/8/3L04/1L06/8/L01L05/8/8 w kqnKQR 0";
+    pos.set_sfen(sfen).unwrap();
+    let before = pos.generate_sfen();
+    let cases = [
+        "8/8/3L04/1L06/8/L01L05/8 w kqnKQR 0",
+        "8/8/3L04/1L06/8/L01L05/8/7x w kqnKQR 0",
+        "8/8/3L04/1L06/8/L01L05/8/8 w kqnKQX 0",
+        "8/8/3L04/1L06/8/L01L05/8/8 x kqnKQR 0",
+        "8/8/3L04/1L06/8/L01L05/8/8 w kqnKQR a",
+    ];
+    for case in cases {
+        assert!(pos.set_sfen(case).is_err());
+        assert_eq!(pos.generate_sfen(), before);
+    }
+}
+
+#[wasm_bindgen_test]
+fn invalid_sfen_error_kind() {
+    let mut pos = ShuuroPosition::new("shuuro");
+    let err = pos
+        .set_sfen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - 1")
+        .unwrap_err();
+    let kind = js_sys::Reflect::get(&err, &JsValue::from_str("kind")).unwrap();
+    assert_eq!(kind.as_string(), Some(String::from("rankCount")));
+}
+
+#[wasm_bindgen_test]
+fn illegal_fight_moves() {
+    let mut pos = ShuuroPosition::new("standard");
+    pos.set_sfen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - 1")
+        .unwrap();
+    let sfen = pos.generate_sfen();
+    for m in ["e2_e5", "e7_e5", "e4_e5", "e2", "e7_e8=X"] {
+        assert!(!pos.is_legal(m));
+        assert!(pos.make_move(String::from(m)).is_null());
+        assert_eq!(pos.generate_sfen(), sfen);
+    }
+    assert!(pos.is_legal("e2_e4"));
+    assert!(!pos.make_move(String::from("e2_e4")).is_null());
+}
+
+#[wasm_bindgen_test]
+fn illegal_placements() {
+    let mut pos = ShuuroPosition::new("standard");
+    let sfen = "4K3/8/8/1L01L04/4L03/6L01/8/8 b RBNNNPPPPPPPPPPPPkqrbbnnp 1";
+    pos.set_sfen(sfen).unwrap();
+    for m in ["K@e1", "k@e1", "g@e8", "k@z9", "k"] {
+        assert_eq!(pos.place(String::from(m)), None);
+        assert_eq!(pos.generate_sfen(), sfen);
+    }
+}
+
+#[wasm_bindgen_test]
+fn shop_rejects_invalid_purchases() {
+    let mut shop = ShuuroShop::new();
+    shop.change_variant(Variant::Standard as u8);
+    let credit = shop.get_credit('w');
+    assert_eq!(Array::from(&shop.buy(String::from("+G"))).length(), 0);
+    assert_eq!(Array::from(&shop.buy(String::from("Q"))).length(), 0);
+    assert_eq!(shop.get_credit('w'), credit);
+    for _ in 0..20 {
+        shop.buy(String::from("+Q"));
+    }
+    assert!(shop.get_credit('w') >= 0);
+    assert_eq!(Array::from(&shop.shop_items('x')).length(), 0);
+}
+
+#[wasm_bindgen_test]
+fn malformed_search_options() {
+    let pos = ShuuroPosition::new("standard");
+    let options = js_sys::JSON::parse(r#"{"depth": "deep"}"#).unwrap();
+    assert!(pos.best_move(options.unchecked_into()).is_err());
+    let options = js_sys::JSON::parse(r#"{"depth": 1}"#).unwrap();
+    assert!(pos.best_move(options.unchecked_into()).is_ok());
+}